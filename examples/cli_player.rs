@@ -26,7 +26,7 @@ fn main() {
     let (tx, rx) = channel::<()>();
     
     let em = md.event_manager();
-    let _ = em.attach(EventType::MediaStateChanged, move |e, _| {
+    let _subscription = em.attach(EventType::MediaStateChanged, move |e, _| {
         match e {
             Event::MediaStateChanged(s) => {
                 println!("State : {:?}", s);
@@ -36,7 +36,7 @@ fn main() {
             },
             _ => (),
         }
-    });
+    }).unwrap();
     
     mdp.set_media(&md);
     
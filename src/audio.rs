@@ -5,14 +5,30 @@
 use vlc_sys as sys;
 use crate::MediaPlayer;
 use crate::TrackDescription;
-use crate::tools::from_cstr;
+use crate::Error;
+use crate::media_player::track_description_list;
+use crate::tools::{from_cstr, from_cstr_ref};
+use crate::tools::to_cstr;
+use std::borrow::Cow;
+use std::marker::PhantomData;
+use std::ops::RangeInclusive;
+use std::ptr;
+use libc::c_void;
 
 pub trait MediaPlayerAudioEx {
     fn get_mute(&self) -> Option<bool>;
     fn set_mute(&self, muted: bool);
     fn get_volume(&self) -> i32;
-    fn set_volume(&self, volume: i32) -> Result<(), ()>;
+    fn set_volume(&self, volume: i32) -> Result<(), Error>;
+    fn get_audio_track(&self) -> Option<i32>;
+    fn set_audio_track(&self, track: i32) -> Result<(), Error>;
+    fn get_audio_track_count(&self) -> i32;
     fn get_audio_track_description(&self) -> Option<Vec<TrackDescription>>;
+    fn set_equalizer(&self, equalizer: Option<&Equalizer>) -> Result<(), Error>;
+    fn set_audio_output(&self, name: &str) -> Result<(), Error>;
+    fn audio_output_device_enum(&self) -> Option<AudioOutputDeviceList>;
+    fn set_audio_output_device(&self, module: Option<&str>, device_id: &str) -> Result<(), Error>;
+    fn get_audio_output_device(&self) -> Option<String>;
 }
 
 impl MediaPlayerAudioEx for MediaPlayer {
@@ -35,25 +51,341 @@ impl MediaPlayerAudioEx for MediaPlayer {
     fn get_volume(&self) -> i32 {
         unsafe{ sys::libvlc_audio_get_volume(self.ptr) }
     }
-    fn set_volume(&self, volume: i32) -> Result<(), ()> {
+    fn set_volume(&self, volume: i32) -> Result<(), Error> {
         unsafe{
-            if sys::libvlc_audio_set_volume(self.ptr, volume) == 0 { Ok(()) }else{ Err(()) }
+            if sys::libvlc_audio_set_volume(self.ptr, volume) == 0 { Ok(()) }else{ Err(Error::last()) }
         }
     }
+    fn get_audio_track(&self) -> Option<i32> {
+        unsafe{
+            let track = sys::libvlc_audio_get_track(self.ptr);
+            if track == -1 { None }else{ Some(track) }
+        }
+    }
+    fn set_audio_track(&self, track: i32) -> Result<(), Error> {
+        unsafe{
+            if sys::libvlc_audio_set_track(self.ptr, track) == 0 { Ok(()) }else{ Err(Error::last()) }
+        }
+    }
+    fn get_audio_track_count(&self) -> i32 {
+        unsafe{ sys::libvlc_audio_get_track_count(self.ptr) }
+    }
     fn get_audio_track_description(&self) -> Option<Vec<TrackDescription>> {
+        unsafe{ track_description_list(sys::libvlc_audio_get_track_description(self.ptr)) }
+    }
+
+    fn set_equalizer(&self, equalizer: Option<&Equalizer>) -> Result<(), Error> {
+        let p = equalizer.map_or(ptr::null_mut(), |eq| eq.ptr);
+        unsafe{
+            if sys::libvlc_media_player_set_equalizer(self.ptr, p) == 0 { Ok(()) }else{ Err(Error::last()) }
+        }
+    }
+
+    fn set_audio_output(&self, name: &str) -> Result<(), Error> {
+        let cstr = to_cstr(name)?;
+        unsafe{
+            if sys::libvlc_audio_output_set(self.ptr, cstr.as_ptr()) == 0 { Ok(()) }else{ Err(Error::last()) }
+        }
+    }
+
+    fn audio_output_device_enum(&self) -> Option<AudioOutputDeviceList> {
+        unsafe{
+            let p = sys::libvlc_audio_output_device_enum(self.ptr);
+            if p.is_null() { None }else{ Some(AudioOutputDeviceList{ptr: p}) }
+        }
+    }
+
+    fn set_audio_output_device(&self, module: Option<&str>, device_id: &str) -> Result<(), Error> {
+        let module = module.map(to_cstr).transpose()?;
+        let device_id = to_cstr(device_id)?;
+        unsafe{
+            sys::libvlc_audio_output_device_set(
+                self.ptr, module.as_ref().map_or(ptr::null(), |m| m.as_ptr()), device_id.as_ptr());
+        }
+        Ok(())
+    }
+
+    fn get_audio_output_device(&self) -> Option<String> {
+        unsafe{
+            let p = sys::libvlc_audio_output_device_get(self.ptr);
+            let s = from_cstr(p);
+            if !p.is_null() { sys::libvlc_free(p as *mut c_void); }
+            s
+        }
+    }
+}
+
+/// List of audio output modules.
+pub struct AudioOutputList {
+    pub(crate) ptr: *mut sys::libvlc_audio_output_t,
+}
+
+impl AudioOutputList {
+    /// Returns raw pointer
+    pub fn raw(&self) -> *mut sys::libvlc_audio_output_t {
+        self.ptr
+    }
+}
+
+impl Drop for AudioOutputList {
+    fn drop(&mut self) {
+        unsafe{ sys::libvlc_audio_output_list_release(self.ptr) };
+    }
+}
+
+impl<'a> IntoIterator for &'a AudioOutputList {
+    type Item = AudioOutputRef<'a>;
+    type IntoIter = AudioOutputListIter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        AudioOutputListIter{ptr: self.ptr, _phantomdata: PhantomData}
+    }
+}
+
+pub struct AudioOutputListIter<'a> {
+    ptr: *mut sys::libvlc_audio_output_t,
+    _phantomdata: PhantomData<&'a sys::libvlc_audio_output_t>,
+}
+
+/// Description of an audio output module.
+/// The strings are owned.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct AudioOutput {
+    pub name:        Option<String>,
+    pub description: Option<String>,
+}
+
+/// Description of an audio output module.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct AudioOutputRef<'a> {
+    pub name:        Option<Cow<'a, str>>,
+    pub description: Option<Cow<'a, str>>,
+}
+
+impl<'a> Iterator for AudioOutputListIter<'a> {
+    type Item = AudioOutputRef<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
         unsafe{
-            let p0 = sys::libvlc_audio_get_track_description(self.ptr);
-            if p0.is_null() { return None; }
-            let mut td = Vec::new();
-            let mut p = p0;
+            if self.ptr.is_null() {
+                return None;
+            }
+            let p = self.ptr;
+            self.ptr = (*p).p_next;
+            Some(AudioOutputRef{
+                name:        from_cstr_ref((*p).psz_name),
+                description: from_cstr_ref((*p).psz_description),
+            })
+        }
+    }
+}
+
+impl<'a> AudioOutputRef<'a> {
+    /// Convert to owned strings.
+    pub fn into_owned(&'a self) -> AudioOutput {
+        AudioOutput {
+            name:        self.name       .as_ref().map(|s| s.clone().into_owned()),
+            description: self.description.as_ref().map(|s| s.clone().into_owned()),
+        }
+    }
+}
+
+/// List of audio output devices.
+pub struct AudioOutputDeviceList {
+    pub(crate) ptr: *mut sys::libvlc_audio_output_device_t,
+}
+
+impl AudioOutputDeviceList {
+    /// Returns raw pointer
+    pub fn raw(&self) -> *mut sys::libvlc_audio_output_device_t {
+        self.ptr
+    }
+}
+
+impl Drop for AudioOutputDeviceList {
+    fn drop(&mut self) {
+        unsafe{ sys::libvlc_audio_output_device_list_release(self.ptr) };
+    }
+}
+
+impl<'a> IntoIterator for &'a AudioOutputDeviceList {
+    type Item = AudioOutputDeviceRef<'a>;
+    type IntoIter = AudioOutputDeviceListIter<'a>;
 
-            while !(*p).p_next.is_null() {
-                td.push(TrackDescription{ id: (*p).i_id, name: from_cstr((*p).psz_name) });
-                p = (*p).p_next;
+    fn into_iter(self) -> Self::IntoIter {
+        AudioOutputDeviceListIter{ptr: self.ptr, _phantomdata: PhantomData}
+    }
+}
+
+pub struct AudioOutputDeviceListIter<'a> {
+    ptr: *mut sys::libvlc_audio_output_device_t,
+    _phantomdata: PhantomData<&'a sys::libvlc_audio_output_device_t>,
+}
+
+/// Description of an audio output device.
+/// The strings are owned.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct AudioOutputDevice {
+    pub device:      Option<String>,
+    pub description: Option<String>,
+}
+
+/// Description of an audio output device.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct AudioOutputDeviceRef<'a> {
+    pub device:      Option<Cow<'a, str>>,
+    pub description: Option<Cow<'a, str>>,
+}
+
+impl<'a> Iterator for AudioOutputDeviceListIter<'a> {
+    type Item = AudioOutputDeviceRef<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        unsafe{
+            if self.ptr.is_null() {
+                return None;
             }
-            sys::libvlc_track_description_list_release(p0);
-            Some(td)
+            let p = self.ptr;
+            self.ptr = (*p).p_next;
+            Some(AudioOutputDeviceRef{
+                device:      from_cstr_ref((*p).psz_device),
+                description: from_cstr_ref((*p).psz_description),
+            })
+        }
+    }
+}
+
+impl<'a> AudioOutputDeviceRef<'a> {
+    /// Convert to owned strings.
+    pub fn into_owned(&'a self) -> AudioOutputDevice {
+        AudioOutputDevice {
+            device:      self.device     .as_ref().map(|s| s.clone().into_owned()),
+            description: self.description.as_ref().map(|s| s.clone().into_owned()),
+        }
+    }
+}
+
+/// Valid range (in dB) for the pre-amplification and band amplification values.
+pub const EQUALIZER_AMP_RANGE: RangeInclusive<f32> = -20.0..=20.0;
+
+/// An audio equalizer, applied to a media player with `MediaPlayerAudioEx::set_equalizer`.
+///
+/// The player keeps a copy of the settings, so changes made afterwards take effect
+/// only once the equalizer is set again.
+pub struct Equalizer {
+    pub(crate) ptr: *mut sys::libvlc_equalizer_t,
+}
+
+unsafe impl Send for Equalizer {}
+
+impl Equalizer {
+    /// Create a new flat equalizer, with all frequency values zeroed.
+    pub fn new() -> Result<Equalizer, Error> {
+        unsafe{
+            let p = sys::libvlc_audio_equalizer_new();
+            if p.is_null() { Err(Error::last()) }else{ Ok(Equalizer{ptr: p}) }
+        }
+    }
+
+    /// Create a new equalizer initialized from a predefined preset.
+    pub fn from_preset(index: u32) -> Result<Equalizer, Error> {
+        if index >= Equalizer::preset_count() {
+            return Err(Error::InvalidIndex);
+        }
+        unsafe{
+            let p = sys::libvlc_audio_equalizer_new_from_preset(index);
+            if p.is_null() { Err(Error::last()) }else{ Ok(Equalizer{ptr: p}) }
+        }
+    }
+
+    /// Create a new equalizer initialized from the preset with the given name.
+    pub fn from_preset_name(name: &str) -> Result<Equalizer, Error> {
+        let index = Equalizer::presets().iter().position(|preset| preset == name).ok_or(Error::InvalidArgument)?;
+        Equalizer::from_preset(index as u32)
+    }
+
+    /// Get the number of equalizer presets.
+    pub fn preset_count() -> u32 {
+        unsafe{ sys::libvlc_audio_equalizer_get_preset_count() }
+    }
+
+    /// Get the name of a particular equalizer preset.
+    pub fn preset_name(index: u32) -> Option<String> {
+        if index >= Equalizer::preset_count() {
+            return None;
+        }
+        unsafe{
+            from_cstr_ref(sys::libvlc_audio_equalizer_get_preset_name(index)).map(|s| s.into_owned())
         }
     }
 
+    /// Get the names of all equalizer presets, in index order.
+    pub fn presets() -> Vec<String> {
+        (0..Equalizer::preset_count()).filter_map(Equalizer::preset_name).collect()
+    }
+
+    /// Get the number of distinct frequency bands for an equalizer.
+    pub fn band_count() -> u32 {
+        unsafe{ sys::libvlc_audio_equalizer_get_band_count() }
+    }
+
+    /// Get a particular equalizer band frequency (in Hz).
+    pub fn band_frequency(index: u32) -> Option<f32> {
+        let freq = unsafe{ sys::libvlc_audio_equalizer_get_band_frequency(index) };
+        if freq == -1f32 { None }else{ Some(freq) }
+    }
+
+    /// Get the frequencies (in Hz) of all bands, in index order.
+    pub fn band_frequencies() -> Vec<f32> {
+        (0..Equalizer::band_count()).filter_map(Equalizer::band_frequency).collect()
+    }
+
+    /// Get the current pre-amplification value (in dB).
+    pub fn preamp(&self) -> f32 {
+        unsafe{ sys::libvlc_audio_equalizer_get_preamp(self.ptr) }
+    }
+
+    /// Set a new pre-amplification value (in dB), which must be within `EQUALIZER_AMP_RANGE`.
+    pub fn set_preamp(&mut self, preamp: f32) -> Result<(), Error> {
+        if !EQUALIZER_AMP_RANGE.contains(&preamp) {
+            return Err(Error::InvalidArgument);
+        }
+        unsafe{
+            if sys::libvlc_audio_equalizer_set_preamp(self.ptr, preamp) == 0 { Ok(()) }else{ Err(Error::last()) }
+        }
+    }
+
+    /// Get the amplification value (in dB) for a particular equalizer frequency band.
+    pub fn amp_at_index(&self, band: u32) -> Option<f32> {
+        if band >= Equalizer::band_count() {
+            return None;
+        }
+        let amp = unsafe{ sys::libvlc_audio_equalizer_get_amp_at_index(self.ptr, band) };
+        if amp.is_nan() { None }else{ Some(amp) }
+    }
+
+    /// Set a new amplification value (in dB) for a particular equalizer frequency band.
+    /// The value must be within `EQUALIZER_AMP_RANGE`.
+    pub fn set_amp_at_index(&mut self, amp: f32, band: u32) -> Result<(), Error> {
+        if band >= Equalizer::band_count() {
+            return Err(Error::InvalidIndex);
+        }
+        if !EQUALIZER_AMP_RANGE.contains(&amp) {
+            return Err(Error::InvalidArgument);
+        }
+        unsafe{
+            if sys::libvlc_audio_equalizer_set_amp_at_index(self.ptr, amp, band) == 0 { Ok(()) }else{ Err(Error::last()) }
+        }
+    }
+
+    /// Returns raw pointer
+    pub fn raw(&self) -> *mut sys::libvlc_equalizer_t {
+        self.ptr
+    }
+}
+
+impl Drop for Equalizer {
+    fn drop(&mut self) {
+        unsafe{ sys::libvlc_audio_equalizer_release(self.ptr) };
+    }
 }
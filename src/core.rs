@@ -4,14 +4,25 @@
 
 use std::ptr;
 use std::borrow::Cow;
+use std::cell::RefCell;
 use std::marker::PhantomData;
 use std::ffi::CString;
 use std::i32;
 use std::convert::TryInto;
-use libc::{c_void, c_char, c_int};
+use std::ops::Deref;
+use std::path::Path;
+use std::sync::mpsc::{channel, Receiver};
+use libc::{c_void, c_char, c_int, c_uint};
 use vlc_sys as sys;
-use crate::tools::{to_cstr, from_cstr, from_cstr_ref};
+use crate::tools::{to_cstr, from_cstr, from_cstr_ref, path_to_cstr};
 use crate::enums::*;
+use crate::Error;
+use crate::Media;
+use crate::audio::{AudioOutputList, AudioOutputDeviceList};
+use crate::media_discoverer::{MediaDiscovererDescription, media_discoverer_list};
+use crate::dialog::DialogData;
+use crate::vlm::VlmRegistry;
+use crate::renderer::{RendererItem, RendererDiscovererDescription, renderer_discoverer_list};
 
 /// Retrieve libvlc version.
 pub fn version() -> String {
@@ -29,7 +40,9 @@ pub fn compiler() -> String {
 
 pub struct Instance {
     pub(crate) ptr: *mut sys::libvlc_instance_t,
-
+    log: RefCell<LogTarget>,
+    pub(crate) dialog: RefCell<Option<Box<DialogData>>>,
+    pub(crate) vlm: VlmRegistry,
 }
 
 unsafe impl Send for Instance {}
@@ -38,12 +51,12 @@ impl Instance {
     /// Create and initialize a libvlc instance with specified args.
     /// Note: args.len() has to be less or equal to i32::MAX
     /// Note: libvlc discourages using arguments as these are not guaranteed to be stable between different versions of libvlc
-    pub fn with_args(args: Option<Vec<String>>) -> Option<Instance> {
+    pub fn with_args(args: Option<Vec<String>>) -> Result<Instance, Error> {
         let args_c_ptr: Vec<*const c_char> ;
         let args_c: Vec<CString>;
         if let Some(argv) = args {
             args_c = argv.into_iter()
-                .map(|x| CString::new(x).expect("Error: Unexpected null byte")).collect();
+                .map(CString::new).collect::<Result<_, _>>()?;
             args_c_ptr = args_c.iter().map(|x| x.as_ptr()).collect();
         } else {
             args_c_ptr = Vec::new();
@@ -58,37 +71,39 @@ impl Instance {
             };
 
             if p.is_null() {
-                return None;
+                return Err(Error::last());
             }
 
-            Some(Instance{ptr: p})
+            Ok(Instance{ptr: p, log: RefCell::new(LogTarget::Default), dialog: RefCell::new(None), vlm: Default::default()})
         }
     }
 
     /// Create and initialize a libvlc instance.
-    pub fn new() -> Option<Instance> {
+    pub fn new() -> Result<Instance, Error> {
         Instance::with_args(None)
     }
 
     /// Try to start a user interface for the libvlc instance.
-    pub fn add_intf(&self, name: &str) -> Result<(), ()> {
-        let cstr = to_cstr(name);
+    pub fn add_intf(&self, name: &str) -> Result<(), Error> {
+        let cstr = to_cstr(name)?;
 
         let result = unsafe{
             sys::libvlc_add_intf(self.ptr, cstr.as_ptr())
         };
 
         if result == 0 { Ok(()) }
-        else { Err(()) }
+        else { Err(Error::last()) }
     }
 
     /// Sets the application name.
     /// LibVLC passes this as the user agent string when a protocol requires it.
-    pub fn set_user_agent(&self, name: &str, http: &str) {
+    pub fn set_user_agent(&self, name: &str, http: &str) -> Result<(), Error> {
+        let name = to_cstr(name)?;
+        let http = to_cstr(http)?;
         unsafe{
-            sys::libvlc_set_user_agent(
-                self.ptr, to_cstr(name).as_ptr(), to_cstr(http).as_ptr());
+            sys::libvlc_set_user_agent(self.ptr, name.as_ptr(), http.as_ptr());
         }
+        Ok(())
     }
 
     /// Waits until an interface causes the instance to exit.
@@ -97,11 +112,14 @@ impl Instance {
     }
 
     /// Sets some meta-information about the application.
-    pub fn set_app_id(&self, id: &str, version: &str, icon: &str) {
+    pub fn set_app_id(&self, id: &str, version: &str, icon: &str) -> Result<(), Error> {
+        let id = to_cstr(id)?;
+        let version = to_cstr(version)?;
+        let icon = to_cstr(icon)?;
         unsafe{
-            sys::libvlc_set_app_id(
-                self.ptr, to_cstr(id).as_ptr(), to_cstr(version).as_ptr(), to_cstr(icon).as_ptr());
+            sys::libvlc_set_app_id(self.ptr, id.as_ptr(), version.as_ptr(), icon.as_ptr());
         }
+        Ok(())
     }
 
     /// Returns a list of audio filters that are available.
@@ -122,6 +140,38 @@ impl Instance {
         }
     }
 
+    /// Returns a list of audio output modules that are available.
+    pub fn audio_output_list_get(&self) -> Option<AudioOutputList> {
+        unsafe{
+            let p = sys::libvlc_audio_output_list_get(self.ptr);
+            if p.is_null() { None }
+            else { Some(AudioOutputList{ptr: p}) }
+        }
+    }
+
+    /// Returns a list of audio output devices for a given audio output module.
+    /// Not all audio outputs support this; an empty list or None does not imply
+    /// that the output cannot be used.
+    pub fn audio_output_device_list_get(&self, aout: &str) -> Option<AudioOutputDeviceList> {
+        // No module name contains a null byte.
+        let cstr = to_cstr(aout).ok()?;
+        unsafe{
+            let p = sys::libvlc_audio_output_device_list_get(self.ptr, cstr.as_ptr());
+            if p.is_null() { None }
+            else { Some(AudioOutputDeviceList{ptr: p}) }
+        }
+    }
+
+    /// Returns the media discovery services of a category.
+    pub fn media_discoverer_list_get(&self, category: DiscovererCategory) -> Vec<MediaDiscovererDescription> {
+        unsafe{ media_discoverer_list(self, category) }
+    }
+
+    /// Returns the renderer discovery services.
+    pub fn renderer_discoverer_list_get(&self) -> Vec<RendererDiscovererDescription> {
+        unsafe{ renderer_discoverer_list(self) }
+    }
+
     /// Returns the VLM event manager
     pub fn vlm_event_manager<'a>(&'a self) -> EventManager<'a> {
         unsafe{
@@ -132,12 +182,35 @@ impl Instance {
     }
 
     /// Set logging callback
+    /// It replaces any previous callback or log file.
     pub fn set_log<F: Fn(LogLevel, Log, Cow<str>) + Send + 'static>(&self, f: F) {
-        let cb: Box<Box<dyn Fn(LogLevel, Log, Cow<str>) + Send + 'static>> = Box::new(Box::new(f));
+        let cb: Box<LogCallback> = Box::new(Box::new(f));
 
         unsafe{
-            sys::libvlc_log_set(self.ptr, Some(logging_cb), Box::into_raw(cb) as *mut _);
+            sys::libvlc_log_set(self.ptr, Some(logging_cb), &*cb as *const LogCallback as *mut c_void);
         }
+        // libvlc does not use the previous target any more once libvlc_log_set() returns.
+        *self.log.borrow_mut() = LogTarget::Callback(cb);
+    }
+
+    /// Unset the logging callback or file.
+    /// Messages are written to the standard error again.
+    pub fn log_unset(&self) {
+        unsafe{ sys::libvlc_log_unset(self.ptr) };
+        *self.log.borrow_mut() = LogTarget::Default;
+    }
+
+    /// Append log messages to a file, instead of a callback.
+    pub fn log_set_file<P: AsRef<Path>>(&self, path: P) -> Result<(), Error> {
+        let path = path_to_cstr(path.as_ref())?;
+        let file = unsafe{ libc::fopen(path.as_ptr(), b"a\0".as_ptr() as *const c_char) };
+        if file.is_null() {
+            return Err(Error::Io(::std::io::Error::last_os_error().kind()));
+        }
+
+        unsafe{ sys::libvlc_log_set_file(self.ptr, file as *mut sys::FILE) };
+        *self.log.borrow_mut() = LogTarget::File(file);
+        Ok(())
     }
 
     /// Returns raw pointer
@@ -149,21 +222,61 @@ impl Instance {
 impl Drop for Instance {
     fn drop(&mut self) {
         unsafe{
+            // Other objects may keep libvlc alive, stop calling into the callbacks dropped with us.
+            if let LogTarget::Default = *self.log.borrow() {}else{
+                sys::libvlc_log_unset(self.ptr);
+            }
+            if let Some(ref dialog) = *self.dialog.borrow() {
+                dialog.forget_pending();
+                sys::libvlc_dialog_set_callbacks(self.ptr, ptr::null(), ptr::null_mut());
+            }
             sys::libvlc_release(self.ptr);
         }
     }
 }
 
+type LogCallback = Box<dyn Fn(LogLevel, Log, Cow<str>) + Send + 'static>;
+
+// Where libvlc currently sends its log messages.
+enum LogTarget {
+    Default,
+    // Only kept alive while libvlc may call it.
+    Callback(#[allow(dead_code)] Box<LogCallback>),
+    File(*mut libc::FILE),
+}
+
+impl Drop for LogTarget {
+    fn drop(&mut self) {
+        if let LogTarget::File(file) = *self {
+            unsafe{ libc::fclose(file) };
+        }
+    }
+}
+
 const BUF_SIZE: usize = 1024; // Write log message to the buffer by vsnprintf.
 unsafe extern "C" fn logging_cb(
     data: *mut c_void, level: c_int, ctx: *const sys::libvlc_log_t, fmt: *const c_char, args: *mut sys::__va_list_tag) {
 
-    let f: &Box<dyn Fn(LogLevel, Log, Cow<str>) + Send + 'static> = ::std::mem::transmute(data);
+    let f = &*(data as *const LogCallback);
     let mut buf: [c_char; BUF_SIZE] = [0; BUF_SIZE];
-
-    sys::vsnprintf(buf.as_mut_ptr(), BUF_SIZE.try_into().unwrap(), fmt, args);
-
-    f((level as u32).into(), Log{ptr: ctx}, from_cstr_ref(buf.as_ptr()).unwrap());
+    // A va_list can only be consumed once. On the supported ABIs va_copy() is a plain
+    // copy of the structure, keep one in case the message does not fit in the buffer.
+    let mut args_copy = *args;
+
+    let len = sys::vsnprintf(buf.as_mut_ptr(), BUF_SIZE.try_into().unwrap(), fmt, args);
+    let msg = if len < 0 {
+        Cow::Borrowed("")
+    }else if (len as usize) < BUF_SIZE {
+        from_cstr_ref(buf.as_ptr()).unwrap()
+    }else{
+        let mut heap: Vec<c_char> = vec![0; len as usize + 1];
+        sys::vsnprintf(heap.as_mut_ptr(), heap.len().try_into().unwrap(), fmt, &mut args_copy);
+        Cow::Owned(from_cstr(heap.as_ptr()).unwrap())
+    };
+
+    // Must not panic across the FFI boundary, treat unknown levels as debug messages.
+    let level = LogLevel::from_raw(level as u32).unwrap_or(LogLevel::Debug);
+    f(level, Log{ptr: ctx}, msg);
 }
 
 /// List of module description.
@@ -278,20 +391,31 @@ pub enum Event {
     MediaPlayerBackward,
     MediaPlayerEndReached,
     MediaPlayerEncounteredError,
-    MediaPlayerTimeChanged,
+    MediaPlayerTimeChanged(i64),
     MediaPlayerPositionChanged(f32),
     MediaPlayerSeekableChanged,
     MediaPlayerPausableChanged,
-    MediaPlayerTitleChanged,
-    MediaPlayerSnapshotTaken,
-    MediaPlayerLengthChanged,
-    MediaPlayerVout,
+    MediaPlayerTitleChanged(i32),
+    MediaPlayerSnapshotTaken(Option<String>),
+    MediaPlayerLengthChanged(i64),
+    MediaPlayerVout(i32),
     MediaPlayerScrambledChanged,
-
-    MediaListItemAdded,
-    MediaListWillAddItem,
-    MediaListItemDeleted,
-    MediaListWillDeleteItem,
+    MediaPlayerESAdded(TrackType, i32),
+    MediaPlayerESDeleted(TrackType, i32),
+    MediaPlayerESSelected(TrackType, i32),
+    MediaPlayerCorked,
+    MediaPlayerUncorked,
+    MediaPlayerMuted,
+    MediaPlayerUnmuted,
+    MediaPlayerAudioVolume(f32),
+    MediaPlayerAudioDevice(Option<String>),
+    MediaPlayerChapterChanged(i32),
+
+    MediaListItemAdded(Media, i32),
+    MediaListWillAddItem(Media, i32),
+    MediaListItemDeleted(Media, i32),
+    MediaListWillDeleteItem(Media, i32),
+    MediaListEndReached,
 
     MediaListViewItemAdded,
     MediaListViewWillAddItem,
@@ -305,6 +429,9 @@ pub enum Event {
     MediaDiscovererStarted,
     MediaDiscovererEnded,
 
+    RendererDiscovererItemAdded(RendererItem),
+    RendererDiscovererItemDeleted(RendererItem),
+
     VlmMediaAdded(Option<String>, Option<String>),
     VlmMediaRemoved(Option<String>, Option<String>),
     VlmMediaChanged(Option<String>, Option<String>),
@@ -315,7 +442,11 @@ pub enum Event {
     VlmMediaInstanceStatusPlaying(Option<String>, Option<String>),
     VlmMediaInstanceStatusPause(Option<String>, Option<String>),
     VlmMediaInstanceStatusEnd(Option<String>, Option<String>),
-    VlmMediaInstanceStatusError(Option<String>, Option<String>)
+    VlmMediaInstanceStatusError(Option<String>, Option<String>),
+
+    /// An event (or event payload) unknown to vlc-rs, e.g. sent by a newer libvlc.
+    /// Carries the raw event type.
+    Unknown(i32),
 }
 
 pub struct EventManager<'a> {
@@ -323,53 +454,129 @@ pub struct EventManager<'a> {
     pub(crate) _phantomdata: ::std::marker::PhantomData<&'a sys::libvlc_event_manager_t>,
 }
 
-impl<'a> EventManager<'a> {
-    pub fn detach(&self, event_type: EventType, registered_callback: *mut c_void) {
-        unsafe { sys::libvlc_event_detach(self.ptr, event_type as i32, Some(event_manager_callback), registered_callback) }
-    }
+type EventCallback = Box<dyn Fn(Event, VLCObject) + Send + 'static>;
 
-    pub fn attach<F>(&self, event_type: EventType, callback: F) -> Result<*mut c_void, ()>
+impl<'a> EventManager<'a> {
+    /// Register a callback for an event notification.
+    /// The callback is detached and freed when the returned subscription is dropped,
+    /// which cannot outlive the object owning this event manager.
+    pub fn attach<F>(&self, event_type: EventType, callback: F) -> Result<EventSubscription<'a>, Error>
         where F: Fn(Event, VLCObject) + Send + 'static
     {
         // Explicit type annotation is needed
-        let callback: Box<Box<dyn Fn(Event, VLCObject) + Send + 'static>> =
-            Box::new(Box::new(callback));
+        let callback: Box<EventCallback> = Box::new(Box::new(callback));
 
-        let raw = Box::into_raw(callback) as *mut c_void;
+        let raw = Box::into_raw(callback);
 
         let result = unsafe{
             sys::libvlc_event_attach(
                 self.ptr, event_type as i32, Some(event_manager_callback),
-                raw)
+                raw as *mut c_void)
         };
 
         if result == 0 {
-            Ok(raw)
+            Ok(EventSubscription{em: self.ptr, event_type, callback: raw, _phantomdata: PhantomData})
         }else{
-            Err(())
+            unsafe{ drop(Box::from_raw(raw)) };
+            Err(Error::last())
         }
     }
 
+    /// Register for a set of event types and receive the events through a channel.
+    /// The events are detached when the returned receiver is dropped.
+    pub fn subscribe(&self, event_types: &[EventType]) -> Result<EventReceiver<'a>, Error> {
+        let (tx, rx) = channel();
+        let subscriptions = event_types.iter().map(|&event_type| {
+            let tx = tx.clone();
+            self.attach(event_type, move |e, _| { let _ = tx.send(e); })
+        }).collect::<Result<Vec<_>, Error>>()?;
+
+        Ok(EventReceiver{receiver: rx, _subscriptions: subscriptions})
+    }
+
     /// Returns raw pointer
     pub fn raw(&self) -> *mut sys::libvlc_event_manager_t {
         self.ptr
     }
 }
 
+/// Receiving end of EventManager::subscribe.
+/// Dereferences to a `std::sync::mpsc::Receiver<Event>`.
+pub struct EventReceiver<'a> {
+    receiver: Receiver<Event>,
+    _subscriptions: Vec<EventSubscription<'a>>,
+}
+
+impl<'a> Deref for EventReceiver<'a> {
+    type Target = Receiver<Event>;
+
+    fn deref(&self) -> &Receiver<Event> {
+        &self.receiver
+    }
+}
+
+/// A callback registered with EventManager::attach.
+/// Dropping it detaches the callback and frees it.
+///
+/// libvlc waits for a running callback to return before detaching it, so a
+/// subscription must not be dropped from within its own callback.
+#[must_use = "the callback is detached as soon as the subscription is dropped"]
+pub struct EventSubscription<'a> {
+    em: *mut sys::libvlc_event_manager_t,
+    event_type: EventType,
+    callback: *mut EventCallback,
+    _phantomdata: PhantomData<&'a sys::libvlc_event_manager_t>,
+}
+
+unsafe impl<'a> Send for EventSubscription<'a> {}
+
+impl<'a> EventSubscription<'a> {
+    /// Type of the event the callback is registered for.
+    pub fn event_type(&self) -> EventType {
+        self.event_type
+    }
+
+    /// Detach the callback, same as dropping the subscription.
+    pub fn detach(self) {}
+}
+
+impl<'a> Drop for EventSubscription<'a> {
+    fn drop(&mut self) {
+        unsafe{
+            sys::libvlc_event_detach(
+                self.em, self.event_type as i32, Some(event_manager_callback),
+                self.callback as *mut c_void);
+            drop(Box::from_raw(self.callback));
+        }
+    }
+}
+
 unsafe extern "C" fn event_manager_callback(pe: *const sys::libvlc_event_t, data: *mut c_void) {
-    let f: &Box<dyn Fn(Event, VLCObject) + Send + 'static> = ::std::mem::transmute(data);
+    let f = &*(data as *const EventCallback);
 
     f(conv_event(pe), VLCObject{ ptr: (*pe).p_obj });
 }
 
+// Take a new reference on a media passed in an event.
+unsafe fn event_media(p: *mut sys::libvlc_media_t) -> Media {
+    sys::libvlc_media_retain(p);
+    Media{ptr: p}
+}
+
 // Convert c-style libvlc_event_t to Event
+// Never panics: events or payloads unknown to vlc-rs are converted to Event::Unknown.
 fn conv_event(pe: *const sys::libvlc_event_t) -> Event {
-    let event_type: EventType = (unsafe{ (*pe).type_ } as u32).into();
+    let raw_type = unsafe{ (*pe).type_ };
+    conv_known_event(pe, raw_type).unwrap_or(Event::Unknown(raw_type))
+}
+
+fn conv_known_event(pe: *const sys::libvlc_event_t, raw_type: i32) -> Option<Event> {
+    let event_type = EventType::from_raw(raw_type as u32)?;
 
-    match event_type {
+    let event = match event_type {
         EventType::MediaMetaChanged => {
             unsafe{
-                Event::MediaMetaChanged((*pe).u.media_meta_changed.meta_type.into())
+                Event::MediaMetaChanged(Meta::from_raw((*pe).u.media_meta_changed.meta_type)?)
             }
         },
         EventType::MediaSubItemAdded => {
@@ -390,8 +597,8 @@ fn conv_event(pe: *const sys::libvlc_event_t) -> Event {
         },
         EventType::MediaStateChanged => {
             unsafe{
-                let new_state: sys::libvlc_state_t = (*pe).u.media_state_changed.new_state.try_into().unwrap();
-                Event::MediaStateChanged(new_state.into())
+                let new_state = (*pe).u.media_state_changed.new_state as sys::libvlc_state_t;
+                Event::MediaStateChanged(State::from_raw(new_state)?)
             }
         },
         EventType::MediaSubItemTreeAdded => {
@@ -433,7 +640,9 @@ fn conv_event(pe: *const sys::libvlc_event_t) -> Event {
             Event::MediaPlayerEncounteredError
         },
         EventType::MediaPlayerTimeChanged => {
-            Event::MediaPlayerTimeChanged
+            unsafe{
+                Event::MediaPlayerTimeChanged((*pe).u.media_player_time_changed.new_time)
+            }
         },
         EventType::MediaPlayerPositionChanged => {
             unsafe{
@@ -447,31 +656,99 @@ fn conv_event(pe: *const sys::libvlc_event_t) -> Event {
             Event::MediaPlayerPausableChanged
         },
         EventType::MediaPlayerTitleChanged => {
-            Event::MediaPlayerTitleChanged
+            unsafe{
+                Event::MediaPlayerTitleChanged((*pe).u.media_player_title_changed.new_title)
+            }
         },
         EventType::MediaPlayerSnapshotTaken => {
-            Event::MediaPlayerSnapshotTaken
+            unsafe{
+                Event::MediaPlayerSnapshotTaken(from_cstr((*pe).u.media_player_snapshot_taken.psz_filename))
+            }
         },
         EventType::MediaPlayerLengthChanged => {
-            Event::MediaPlayerLengthChanged
+            unsafe{
+                Event::MediaPlayerLengthChanged((*pe).u.media_player_length_changed.new_length)
+            }
         },
         EventType::MediaPlayerVout => {
-            Event::MediaPlayerVout
+            unsafe{
+                Event::MediaPlayerVout((*pe).u.media_player_vout.new_count)
+            }
         },
         EventType::MediaPlayerScrambledChanged => {
             Event::MediaPlayerScrambledChanged
         },
+        EventType::MediaPlayerESAdded => {
+            unsafe{
+                let es = (*pe).u.media_player_es_changed;
+                Event::MediaPlayerESAdded(TrackType::from_raw(es.i_type)?, es.i_id)
+            }
+        },
+        EventType::MediaPlayerESDeleted => {
+            unsafe{
+                let es = (*pe).u.media_player_es_changed;
+                Event::MediaPlayerESDeleted(TrackType::from_raw(es.i_type)?, es.i_id)
+            }
+        },
+        EventType::MediaPlayerESSelected => {
+            unsafe{
+                let es = (*pe).u.media_player_es_changed;
+                Event::MediaPlayerESSelected(TrackType::from_raw(es.i_type)?, es.i_id)
+            }
+        },
+        EventType::MediaPlayerCorked => {
+            Event::MediaPlayerCorked
+        },
+        EventType::MediaPlayerUncorked => {
+            Event::MediaPlayerUncorked
+        },
+        EventType::MediaPlayerMuted => {
+            Event::MediaPlayerMuted
+        },
+        EventType::MediaPlayerUnmuted => {
+            Event::MediaPlayerUnmuted
+        },
+        EventType::MediaPlayerAudioVolume => {
+            unsafe{
+                Event::MediaPlayerAudioVolume((*pe).u.media_player_audio_volume.volume)
+            }
+        },
+        EventType::MediaPlayerAudioDevice => {
+            unsafe{
+                Event::MediaPlayerAudioDevice(from_cstr((*pe).u.media_player_audio_device.device))
+            }
+        },
+        EventType::MediaPlayerChapterChanged => {
+            unsafe{
+                Event::MediaPlayerChapterChanged((*pe).u.media_player_chapter_changed.new_chapter)
+            }
+        },
         EventType::MediaListItemAdded => {
-            Event::MediaListItemAdded
+            unsafe{
+                let e = (*pe).u.media_list_item_added;
+                Event::MediaListItemAdded(event_media(e.item), e.index)
+            }
         },
         EventType::MediaListWillAddItem => {
-            Event::MediaListWillAddItem
+            unsafe{
+                let e = (*pe).u.media_list_will_add_item;
+                Event::MediaListWillAddItem(event_media(e.item), e.index)
+            }
         },
         EventType::MediaListItemDeleted => {
-            Event::MediaListItemDeleted
+            unsafe{
+                let e = (*pe).u.media_list_item_deleted;
+                Event::MediaListItemDeleted(event_media(e.item), e.index)
+            }
         },
         EventType::MediaListWillDeleteItem => {
-            Event::MediaListWillDeleteItem
+            unsafe{
+                let e = (*pe).u.media_list_will_delete_item;
+                Event::MediaListWillDeleteItem(event_media(e.item), e.index)
+            }
+        },
+        EventType::MediaListEndReached => {
+            Event::MediaListEndReached
         },
         EventType::MediaListViewItemAdded => {
             Event::MediaListViewItemAdded
@@ -500,6 +777,16 @@ fn conv_event(pe: *const sys::libvlc_event_t) -> Event {
         EventType::MediaDiscovererEnded => {
             Event::MediaDiscovererEnded
         },
+        EventType::RendererDiscovererItemAdded => {
+            unsafe{
+                Event::RendererDiscovererItemAdded(RendererItem::hold((*pe).u.renderer_discoverer_item_added.item))
+            }
+        },
+        EventType::RendererDiscovererItemDeleted => {
+            unsafe{
+                Event::RendererDiscovererItemDeleted(RendererItem::hold((*pe).u.renderer_discoverer_item_deleted.item))
+            }
+        },
         EventType::VlmMediaAdded => {
             unsafe {
                 Event::VlmMediaAdded(from_cstr((*pe).u.vlm_media_event.psz_instance_name), from_cstr((*pe).u.vlm_media_event.psz_media_name))
@@ -555,7 +842,9 @@ fn conv_event(pe: *const sys::libvlc_event_t) -> Event {
                 Event::VlmMediaInstanceStatusError(from_cstr((*pe).u.vlm_media_event.psz_instance_name), from_cstr((*pe).u.vlm_media_event.psz_media_name))
             }
         },
-    }
+    };
+
+    Some(event)
 }
 
 pub struct VLCObject {
@@ -569,11 +858,62 @@ impl VLCObject {
     }
 }
 
+/// Context of a log message.
+/// It is only valid during the logging callback.
 pub struct Log {
     pub(crate) ptr: *const sys::libvlc_log_t
 }
 
 impl Log {
+    fn context(&self) -> (*const c_char, *const c_char, c_uint) {
+        let mut module = ptr::null();
+        let mut file = ptr::null();
+        let mut line = 0;
+        unsafe{ sys::libvlc_log_get_context(self.ptr, &mut module, &mut file, &mut line) };
+        (module, file, line)
+    }
+
+    fn object(&self) -> (*const c_char, *const c_char, usize) {
+        let mut name = ptr::null();
+        let mut header = ptr::null();
+        let mut id = 0;
+        unsafe{ sys::libvlc_log_get_object(self.ptr, &mut name, &mut header, &mut id) };
+        (name, header, id)
+    }
+
+    /// Name of the module emitting the message.
+    pub fn module(&self) -> Option<String> {
+        unsafe{ from_cstr(self.context().0) }
+    }
+
+    /// Source file emitting the message.
+    pub fn file(&self) -> Option<String> {
+        unsafe{ from_cstr(self.context().1) }
+    }
+
+    /// Source line emitting the message, if known.
+    pub fn line(&self) -> Option<u32> {
+        match self.context().2 {
+            0 => None,
+            line => Some(line),
+        }
+    }
+
+    /// Type name of the object emitting the message, like "input" or "decoder".
+    pub fn object_type(&self) -> Option<String> {
+        unsafe{ from_cstr(self.object().0) }
+    }
+
+    /// Header of the object emitting the message, if any.
+    pub fn object_header(&self) -> Option<String> {
+        unsafe{ from_cstr(self.object().1) }
+    }
+
+    /// Identifier of the object emitting the message, unique among live objects.
+    pub fn object_id(&self) -> usize {
+        self.object().2
+    }
+
     /// Returns raw pointer
     pub fn raw(&self) -> *const sys::libvlc_log_t {
         self.ptr
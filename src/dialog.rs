@@ -0,0 +1,499 @@
+// Copyright (c) 2015 T. Okubo
+// This file is part of vlc-rs.
+// Licensed under the MIT license, see the LICENSE file.
+
+use vlc_sys as sys;
+use crate::{Instance, Error};
+use crate::enums::DialogQuestionType;
+use crate::tools::{to_cstr, from_cstr};
+use std::collections::HashMap;
+use std::ptr;
+use std::sync::{Arc, Mutex};
+use libc::{c_void, c_char, c_int};
+
+/// Handler of the dialogs raised by libvlc, registered with `Instance::set_dialog_handler`.
+///
+/// Callbacks are called from libvlc threads. Login and question dialogs block the
+/// requesting input until they are answered, which can be done from any thread.
+pub trait DialogHandler: Send + Sync + 'static {
+    /// An error message to display; no answer is expected.
+    fn display_error(&self, _title: &str, _text: &str) {}
+
+    /// Credentials are requested.
+    /// The dialog is dismissed if it is dropped without an answer.
+    fn display_login(&self, dialog: LoginDialog);
+
+    /// A question is asked.
+    /// The dialog is dismissed if it is dropped without an answer.
+    fn display_question(&self, dialog: QuestionDialog);
+
+    /// The progress of an operation is displayed.
+    /// The dialog stays open until libvlc cancels it, even if it is dropped.
+    fn display_progress(&self, _dialog: ProgressDialog) {}
+
+    /// libvlc closed a dialog; it is dismissed and can no longer be answered.
+    fn cancel(&self, _id: DialogId) {}
+
+    /// The position (0.0 to 1.0) and text of a progress dialog changed.
+    fn update_progress(&self, _id: DialogId, _position: f32, _text: &str) {}
+}
+
+/// Identifies a dialog across the DialogHandler callbacks.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct DialogId(u64);
+
+// Dialogs neither answered nor dismissed yet; each one must be posted exactly once.
+// libvlc may reuse the address of a closed dialog for a new one, so dialogs are
+// identified by a token of their own.
+#[derive(Default)]
+struct PendingDialogs {
+    next_token: u64,
+    dialogs: HashMap<u64, *mut sys::libvlc_dialog_id>,
+}
+
+unsafe impl Send for PendingDialogs {}
+
+impl PendingDialogs {
+    fn insert(&mut self, id: *mut sys::libvlc_dialog_id) -> u64 {
+        self.next_token += 1;
+        self.dialogs.insert(self.next_token, id);
+        self.next_token
+    }
+
+    fn token(&self, id: *mut sys::libvlc_dialog_id) -> Option<u64> {
+        self.dialogs.iter().find(|&(_, &p)| p == id).map(|(&token, _)| token)
+    }
+}
+
+struct Dialog {
+    token: u64,
+    pending: Arc<Mutex<PendingDialogs>>,
+}
+
+impl Dialog {
+    fn id(&self) -> DialogId {
+        DialogId(self.token)
+    }
+
+    fn post<F: FnOnce(*mut sys::libvlc_dialog_id) -> c_int>(&self, f: F) -> Result<(), Error> {
+        let mut pending = self.pending.lock().unwrap();
+        let id = pending.dialogs.remove(&self.token).ok_or(Error::Cancelled)?;
+        if f(id) == 0 { Ok(()) }else{ Err(Error::last()) }
+    }
+
+    fn dismiss(&self) -> Result<(), Error> {
+        self.post(|id| unsafe{ sys::libvlc_dialog_dismiss(id) })
+    }
+}
+
+/// Dialog asking for a username and a password.
+pub struct LoginDialog {
+    dialog: Dialog,
+    title: String,
+    text: String,
+    default_username: Option<String>,
+    ask_store: bool,
+}
+
+impl LoginDialog {
+    pub fn id(&self) -> DialogId {
+        self.dialog.id()
+    }
+
+    pub fn title(&self) -> &str {
+        &self.title
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Username to propose, if any.
+    pub fn default_username(&self) -> Option<&str> {
+        self.default_username.as_deref()
+    }
+
+    /// Whether the user may choose to store the credentials.
+    pub fn ask_store(&self) -> bool {
+        self.ask_store
+    }
+
+    /// Answer with credentials; `store` keeps them in the credentials store.
+    pub fn post_login(self, username: &str, password: &str, store: bool) -> Result<(), Error> {
+        let username = to_cstr(username)?;
+        let password = to_cstr(password)?;
+        self.dialog.post(|id| unsafe{
+            sys::libvlc_dialog_post_login(id, username.as_ptr(), password.as_ptr(), store)
+        })
+    }
+
+    /// Dismiss the dialog without credentials.
+    pub fn dismiss(self) -> Result<(), Error> {
+        self.dialog.dismiss()
+    }
+}
+
+impl Drop for LoginDialog {
+    fn drop(&mut self) {
+        let _ = self.dialog.dismiss();
+    }
+}
+
+/// Dialog asking a question, answered with one of up to two actions.
+pub struct QuestionDialog {
+    dialog: Dialog,
+    title: String,
+    text: String,
+    question_type: DialogQuestionType,
+    cancel: Option<String>,
+    action1: Option<String>,
+    action2: Option<String>,
+}
+
+impl QuestionDialog {
+    pub fn id(&self) -> DialogId {
+        self.dialog.id()
+    }
+
+    pub fn title(&self) -> &str {
+        &self.title
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn question_type(&self) -> DialogQuestionType {
+        self.question_type
+    }
+
+    /// Label of the cancel button.
+    pub fn cancel_label(&self) -> Option<&str> {
+        self.cancel.as_deref()
+    }
+
+    /// Label of the first action, if any.
+    pub fn action1(&self) -> Option<&str> {
+        self.action1.as_deref()
+    }
+
+    /// Label of the second action, if any.
+    pub fn action2(&self) -> Option<&str> {
+        self.action2.as_deref()
+    }
+
+    /// Answer with an action: 1 for action1, 2 for action2.
+    pub fn post_action(self, action: i32) -> Result<(), Error> {
+        let available = match action {
+            1 => self.action1.is_some(),
+            2 => self.action2.is_some(),
+            _ => false,
+        };
+        if !available {
+            return Err(Error::InvalidArgument);
+        }
+        self.dialog.post(|id| unsafe{ sys::libvlc_dialog_post_action(id, action) })
+    }
+
+    /// Dismiss the dialog, like the cancel button.
+    pub fn dismiss(self) -> Result<(), Error> {
+        self.dialog.dismiss()
+    }
+}
+
+impl Drop for QuestionDialog {
+    fn drop(&mut self) {
+        let _ = self.dialog.dismiss();
+    }
+}
+
+/// Dialog displaying the progress of an operation.
+/// Further progress is notified by DialogHandler::update_progress.
+pub struct ProgressDialog {
+    dialog: Dialog,
+    title: String,
+    text: String,
+    indeterminate: bool,
+    position: f32,
+    cancel: Option<String>,
+}
+
+impl ProgressDialog {
+    pub fn id(&self) -> DialogId {
+        self.dialog.id()
+    }
+
+    pub fn title(&self) -> &str {
+        &self.title
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Whether the progress is unknown, in which case position() is meaningless.
+    pub fn is_indeterminate(&self) -> bool {
+        self.indeterminate
+    }
+
+    /// Initial position, from 0.0 to 1.0.
+    pub fn position(&self) -> f32 {
+        self.position
+    }
+
+    /// Label of the cancel button, None if the operation can't be cancelled.
+    pub fn cancel_label(&self) -> Option<&str> {
+        self.cancel.as_deref()
+    }
+
+    /// Cancel the operation.
+    pub fn dismiss(self) -> Result<(), Error> {
+        if self.cancel.is_none() {
+            return Err(Error::InvalidArgument);
+        }
+        self.dialog.dismiss()
+    }
+}
+
+/// A DialogHandler for non-interactive use.
+/// It answers login dialogs with fixed credentials, question dialogs with a fixed
+/// action, and dismisses them when no answer is configured.
+#[derive(Clone, Default, Debug)]
+pub struct AutoResponder {
+    credentials: Option<(String, String)>,
+    action: Option<i32>,
+}
+
+impl AutoResponder {
+    /// Create an AutoResponder dismissing every dialog.
+    pub fn new() -> AutoResponder {
+        Default::default()
+    }
+
+    /// Answer login dialogs with these credentials.
+    /// They are not stored.
+    pub fn credentials(mut self, username: &str, password: &str) -> AutoResponder {
+        self.credentials = Some((username.to_owned(), password.to_owned()));
+        self
+    }
+
+    /// Answer question dialogs with this action (1 or 2) when they offer it.
+    pub fn action(mut self, action: i32) -> AutoResponder {
+        self.action = Some(action);
+        self
+    }
+}
+
+impl DialogHandler for AutoResponder {
+    fn display_login(&self, dialog: LoginDialog) {
+        let _ = match self.credentials {
+            Some((ref username, ref password)) => dialog.post_login(username, password, false),
+            None => dialog.dismiss(),
+        };
+    }
+
+    fn display_question(&self, dialog: QuestionDialog) {
+        let _ = match self.action {
+            Some(1) if dialog.action1().is_some() => dialog.post_action(1),
+            Some(2) if dialog.action2().is_some() => dialog.post_action(2),
+            _ => dialog.dismiss(),
+        };
+    }
+}
+
+pub(crate) struct DialogData {
+    handler: Box<dyn DialogHandler>,
+    pending: Arc<Mutex<PendingDialogs>>,
+}
+
+impl Drop for DialogData {
+    fn drop(&mut self) {
+        self.forget_pending();
+    }
+}
+
+impl DialogData {
+    fn open(&self, id: *mut sys::libvlc_dialog_id) -> Dialog {
+        let token = self.pending.lock().unwrap().insert(id);
+        Dialog{token, pending: self.pending.clone()}
+    }
+
+    // libvlc cancels the pending dialogs itself when the callbacks change, without
+    // calling pf_cancel: forget them, so that the handles left return Cancelled.
+    pub(crate) fn forget_pending(&self) {
+        self.pending.lock().unwrap().dialogs.clear();
+    }
+
+    // The pending dialog libvlc refers to, if it was not answered yet.
+    fn find(&self, id: *mut sys::libvlc_dialog_id) -> Option<Dialog> {
+        let token = self.pending.lock().unwrap().token(id)?;
+        Some(Dialog{token, pending: self.pending.clone()})
+    }
+}
+
+impl Instance {
+    /// Register a handler for the dialogs raised by libvlc, replacing any previous one.
+    /// Without handler, libvlc does not display dialogs and logins or questions fail.
+    pub fn set_dialog_handler<H: DialogHandler>(&self, handler: H) {
+        let data = Box::new(DialogData{handler: Box::new(handler), pending: Default::default()});
+        let cbs = sys::libvlc_dialog_cbs {
+            pf_display_error: Some(dialog_cb_display_error),
+            pf_display_login: Some(dialog_cb_display_login),
+            pf_display_question: Some(dialog_cb_display_question),
+            pf_display_progress: Some(dialog_cb_display_progress),
+            pf_cancel: Some(dialog_cb_cancel),
+            pf_update_progress: Some(dialog_cb_update_progress),
+        };
+        if let Some(ref old) = *self.dialog.borrow() {
+            old.forget_pending();
+        }
+        unsafe{
+            sys::libvlc_dialog_set_callbacks(self.ptr, &cbs, &*data as *const DialogData as *mut c_void);
+        }
+        // The previous callbacks are not called any more; dialogs they opened meanwhile
+        // are forgotten when their data is dropped.
+        *self.dialog.borrow_mut() = Some(data);
+    }
+
+    /// Unregister the dialog handler; pending dialogs are cancelled.
+    pub fn unset_dialog_handler(&self) {
+        if let Some(ref old) = *self.dialog.borrow() {
+            old.forget_pending();
+        }
+        unsafe{ sys::libvlc_dialog_set_callbacks(self.ptr, ptr::null(), ptr::null_mut()) };
+        *self.dialog.borrow_mut() = None;
+    }
+}
+
+fn string(p: *const c_char) -> String {
+    unsafe{ from_cstr(p) }.unwrap_or_default()
+}
+
+fn label(p: *const c_char) -> Option<String> {
+    unsafe{ from_cstr(p) }.filter(|s| !s.is_empty())
+}
+
+unsafe extern "C" fn dialog_cb_display_error(data: *mut c_void, title: *const c_char, text: *const c_char) {
+    let data = &*(data as *const DialogData);
+    data.handler.display_error(&string(title), &string(text));
+}
+
+unsafe extern "C" fn dialog_cb_display_login(
+    data: *mut c_void, id: *mut sys::libvlc_dialog_id, title: *const c_char, text: *const c_char,
+    default_username: *const c_char, ask_store: bool) {
+
+    let data = &*(data as *const DialogData);
+    data.handler.display_login(LoginDialog{
+        dialog: data.open(id),
+        title: string(title),
+        text: string(text),
+        default_username: label(default_username),
+        ask_store,
+    });
+}
+
+unsafe extern "C" fn dialog_cb_display_question(
+    data: *mut c_void, id: *mut sys::libvlc_dialog_id, title: *const c_char, text: *const c_char,
+    question_type: sys::libvlc_dialog_question_type, cancel: *const c_char,
+    action1: *const c_char, action2: *const c_char) {
+
+    let data = &*(data as *const DialogData);
+    let dialog = data.open(id);
+    match DialogQuestionType::from_raw(question_type) {
+        Some(question_type) => data.handler.display_question(QuestionDialog{
+            dialog,
+            title: string(title),
+            text: string(text),
+            question_type,
+            cancel: label(cancel),
+            action1: label(action1),
+            action2: label(action2),
+        }),
+        None => { let _ = dialog.dismiss(); },
+    }
+}
+
+unsafe extern "C" fn dialog_cb_display_progress(
+    data: *mut c_void, id: *mut sys::libvlc_dialog_id, title: *const c_char, text: *const c_char,
+    indeterminate: bool, position: f32, cancel: *const c_char) {
+
+    let data = &*(data as *const DialogData);
+    data.handler.display_progress(ProgressDialog{
+        dialog: data.open(id),
+        title: string(title),
+        text: string(text),
+        indeterminate,
+        position,
+        cancel: label(cancel),
+    });
+}
+
+unsafe extern "C" fn dialog_cb_cancel(data: *mut c_void, id: *mut sys::libvlc_dialog_id) {
+    let data = &*(data as *const DialogData);
+    // Already answered dialogs were forgotten already.
+    if let Some(dialog) = data.find(id) {
+        if dialog.dismiss().is_ok() {
+            data.handler.cancel(dialog.id());
+        }
+    }
+}
+
+unsafe extern "C" fn dialog_cb_update_progress(
+    data: *mut c_void, id: *mut sys::libvlc_dialog_id, position: f32, text: *const c_char) {
+
+    let data = &*(data as *const DialogData);
+    if let Some(dialog) = data.find(id) {
+        data.handler.update_progress(dialog.id(), position, &string(text));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn data() -> DialogData {
+        DialogData{handler: Box::new(AutoResponder::new()), pending: Default::default()}
+    }
+
+    // Never passed to libvlc by these tests.
+    fn fake_id() -> *mut sys::libvlc_dialog_id {
+        ptr::NonNull::dangling().as_ptr()
+    }
+
+    #[test]
+    fn post_once() {
+        let data = data();
+        let dialog = data.open(fake_id());
+        assert_eq!(dialog.post(|id| { assert_eq!(id, fake_id()); 0 }), Ok(()));
+        assert_eq!(dialog.post(|_| panic!("posted twice")), Err(Error::Cancelled));
+    }
+
+    #[test]
+    fn post_after_forget() {
+        let data = data();
+        let dialog = data.open(fake_id());
+        data.forget_pending();
+        assert_eq!(dialog.post(|_| panic!("posted a forgotten dialog")), Err(Error::Cancelled));
+        assert!(data.find(fake_id()).is_none());
+    }
+
+    #[test]
+    fn post_after_drop() {
+        let data = data();
+        let dialog = data.open(fake_id());
+        drop(data);
+        assert_eq!(dialog.post(|_| panic!("posted a dropped dialog")), Err(Error::Cancelled));
+    }
+
+    #[test]
+    fn reused_address() {
+        let data = data();
+        let old = data.open(fake_id());
+        assert_eq!(old.post(|_| 0), Ok(()));
+        // libvlc reuses the address for a new dialog, the old handle must not answer it.
+        let new = data.open(fake_id());
+        assert_ne!(old.id(), new.id());
+        assert_eq!(old.post(|_| panic!("answered the new dialog")), Err(Error::Cancelled));
+        assert_eq!(data.find(fake_id()).map(|d| d.id()), Some(new.id()));
+    }
+}
@@ -14,23 +14,33 @@ macro_rules! define_enum {
             )*
         }
 
-        impl From<sys::$original_type> for $enum_name {
-            fn from(a: sys::$original_type) -> Self {
+        impl $enum_name {
+            /// Convert a raw libvlc value, or return None if it is unknown to vlc-rs.
+            pub fn from_raw(a: sys::$original_type) -> Option<Self> {
                 match a {
                     $(
-                        sys::$c_value => Self::$value,
+                        sys::$c_value => Some(Self::$value),
                     )*
-                    _ => unreachable!(),
+                    _ => None,
                 }
             }
         }
+
+        impl ::std::convert::TryFrom<sys::$original_type> for $enum_name {
+            type Error = sys::$original_type;
+
+            /// Fails with the raw value if it is unknown to vlc-rs, e.g. sent by a newer libvlc.
+            fn try_from(a: sys::$original_type) -> Result<Self, sys::$original_type> {
+                Self::from_raw(a).ok_or(a)
+            }
+        }
     }
 }
 
 define_enum!(
     LogLevel, libvlc_log_level;
     Debug = libvlc_log_level_LIBVLC_DEBUG,
-    Dotice = libvlc_log_level_LIBVLC_NOTICE,
+    Notice = libvlc_log_level_LIBVLC_NOTICE,
     Warning = libvlc_log_level_LIBVLC_WARNING,
     Error = libvlc_log_level_LIBVLC_ERROR,
 );
@@ -106,15 +116,77 @@ define_enum!(
     Gamma = libvlc_video_adjust_option_t_libvlc_adjust_Gamma,
 );
 
+define_enum!(
+    MarqueeOption, libvlc_video_marquee_option_t;
+    Enable = libvlc_video_marquee_option_t_libvlc_marquee_Enable,
+    Text = libvlc_video_marquee_option_t_libvlc_marquee_Text,
+    Color = libvlc_video_marquee_option_t_libvlc_marquee_Color,
+    Opacity = libvlc_video_marquee_option_t_libvlc_marquee_Opacity,
+    Position = libvlc_video_marquee_option_t_libvlc_marquee_Position,
+    Refresh = libvlc_video_marquee_option_t_libvlc_marquee_Refresh,
+    Size = libvlc_video_marquee_option_t_libvlc_marquee_Size,
+    Timeout = libvlc_video_marquee_option_t_libvlc_marquee_Timeout,
+    X = libvlc_video_marquee_option_t_libvlc_marquee_X,
+    Y = libvlc_video_marquee_option_t_libvlc_marquee_Y,
+);
+
+define_enum!(
+    LogoOption, libvlc_video_logo_option_t;
+    Enable = libvlc_video_logo_option_t_libvlc_logo_enable,
+    File = libvlc_video_logo_option_t_libvlc_logo_file,
+    X = libvlc_video_logo_option_t_libvlc_logo_x,
+    Y = libvlc_video_logo_option_t_libvlc_logo_y,
+    Delay = libvlc_video_logo_option_t_libvlc_logo_delay,
+    Repeat = libvlc_video_logo_option_t_libvlc_logo_repeat,
+    Opacity = libvlc_video_logo_option_t_libvlc_logo_opacity,
+    Position = libvlc_video_logo_option_t_libvlc_logo_position,
+);
+
+define_enum!(
+    PlaybackMode, libvlc_playback_mode_t;
+    Default = libvlc_playback_mode_t_libvlc_playback_mode_default,
+    Loop = libvlc_playback_mode_t_libvlc_playback_mode_loop,
+    Repeat = libvlc_playback_mode_t_libvlc_playback_mode_repeat,
+);
+
+define_enum!(
+    MediaSlaveType, libvlc_media_slave_type_t;
+    Subtitle = libvlc_media_slave_type_t_libvlc_media_slave_type_subtitle,
+    Audio = libvlc_media_slave_type_t_libvlc_media_slave_type_audio,
+);
+
 // libvlc 3.0
-// define_enum!(
-//     ParseFlag, libvlc_media_parse_flag_t;
-//     DoInteract = libvlc_media_parse_flag_t_libvlc_media_do_interact,
-//     FetchLocal = libvlc_media_parse_flag_t_libvlc_media_fetch_local,
-//     FetchNetwork = libvlc_media_parse_flag_t_libvlc_media_fetch_network,
-//     ParseLocal = libvlc_media_parse_flag_t_libvlc_media_parse_local,
-//     ParseNetwork = libvlc_media_parse_flag_t_libvlc_media_parse_network,
-// );
+define_enum!(
+    ParseFlag, libvlc_media_parse_flag_t;
+    DoInteract = libvlc_media_parse_flag_t_libvlc_media_do_interact,
+    FetchLocal = libvlc_media_parse_flag_t_libvlc_media_fetch_local,
+    FetchNetwork = libvlc_media_parse_flag_t_libvlc_media_fetch_network,
+    ParseLocal = libvlc_media_parse_flag_t_libvlc_media_parse_local,
+    ParseNetwork = libvlc_media_parse_flag_t_libvlc_media_parse_network,
+);
+
+define_enum!(
+    ParsedStatus, libvlc_media_parsed_status_t;
+    Skipped = libvlc_media_parsed_status_t_libvlc_media_parsed_status_skipped,
+    Failed = libvlc_media_parsed_status_t_libvlc_media_parsed_status_failed,
+    Timeout = libvlc_media_parsed_status_t_libvlc_media_parsed_status_timeout,
+    Done = libvlc_media_parsed_status_t_libvlc_media_parsed_status_done,
+);
+
+define_enum!(
+    DialogQuestionType, libvlc_dialog_question_type;
+    Normal = libvlc_dialog_question_type_LIBVLC_DIALOG_QUESTION_NORMAL,
+    Warning = libvlc_dialog_question_type_LIBVLC_DIALOG_QUESTION_WARNING,
+    Critical = libvlc_dialog_question_type_LIBVLC_DIALOG_QUESTION_CRITICAL,
+);
+
+define_enum!(
+    DiscovererCategory, libvlc_media_discoverer_category_t;
+    Devices = libvlc_media_discoverer_category_t_libvlc_media_discoverer_devices,
+    Lan = libvlc_media_discoverer_category_t_libvlc_media_discoverer_lan,
+    Podcasts = libvlc_media_discoverer_category_t_libvlc_media_discoverer_podcasts,
+    LocalDirs = libvlc_media_discoverer_category_t_libvlc_media_discoverer_localdirs,
+);
 
 define_enum!(
     EventType, libvlc_event_e;
@@ -145,10 +217,21 @@ define_enum!(
     MediaPlayerLengthChanged = libvlc_event_e_libvlc_MediaPlayerLengthChanged,
     MediaPlayerVout = libvlc_event_e_libvlc_MediaPlayerVout,
     MediaPlayerScrambledChanged = libvlc_event_e_libvlc_MediaPlayerScrambledChanged,
+    MediaPlayerESAdded = libvlc_event_e_libvlc_MediaPlayerESAdded,
+    MediaPlayerESDeleted = libvlc_event_e_libvlc_MediaPlayerESDeleted,
+    MediaPlayerESSelected = libvlc_event_e_libvlc_MediaPlayerESSelected,
+    MediaPlayerCorked = libvlc_event_e_libvlc_MediaPlayerCorked,
+    MediaPlayerUncorked = libvlc_event_e_libvlc_MediaPlayerUncorked,
+    MediaPlayerMuted = libvlc_event_e_libvlc_MediaPlayerMuted,
+    MediaPlayerUnmuted = libvlc_event_e_libvlc_MediaPlayerUnmuted,
+    MediaPlayerAudioVolume = libvlc_event_e_libvlc_MediaPlayerAudioVolume,
+    MediaPlayerAudioDevice = libvlc_event_e_libvlc_MediaPlayerAudioDevice,
+    MediaPlayerChapterChanged = libvlc_event_e_libvlc_MediaPlayerChapterChanged,
     MediaListItemAdded = libvlc_event_e_libvlc_MediaListItemAdded,
     MediaListWillAddItem = libvlc_event_e_libvlc_MediaListWillAddItem,
     MediaListItemDeleted = libvlc_event_e_libvlc_MediaListItemDeleted,
     MediaListWillDeleteItem = libvlc_event_e_libvlc_MediaListWillDeleteItem,
+    MediaListEndReached = libvlc_event_e_libvlc_MediaListEndReached,
     MediaListViewItemAdded = libvlc_event_e_libvlc_MediaListViewItemAdded,
     MediaListViewWillAddItem = libvlc_event_e_libvlc_MediaListViewWillAddItem,
     MediaListViewItemDeleted = libvlc_event_e_libvlc_MediaListViewItemDeleted,
@@ -158,6 +241,8 @@ define_enum!(
     MediaListPlayerStopped = libvlc_event_e_libvlc_MediaListPlayerStopped,
     MediaDiscovererStarted = libvlc_event_e_libvlc_MediaDiscovererStarted,
     MediaDiscovererEnded = libvlc_event_e_libvlc_MediaDiscovererEnded,
+    RendererDiscovererItemAdded = libvlc_event_e_libvlc_RendererDiscovererItemAdded,
+    RendererDiscovererItemDeleted = libvlc_event_e_libvlc_RendererDiscovererItemDeleted,
     VlmMediaAdded = libvlc_event_e_libvlc_VlmMediaAdded,
     VlmMediaRemoved = libvlc_event_e_libvlc_VlmMediaRemoved,
     VlmMediaChanged = libvlc_event_e_libvlc_VlmMediaChanged,
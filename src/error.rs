@@ -0,0 +1,66 @@
+// Copyright (c) 2015 T. Okubo
+// This file is part of vlc-rs.
+// Licensed under the MIT license, see the LICENSE file.
+
+use std::error;
+use std::ffi::NulError;
+use std::fmt;
+use std::io;
+use crate::{errmsg, clearerr};
+
+/// Error returned by fallible calls.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Error {
+    /// A string argument contained a null byte.
+    NulByte,
+    /// An index or identifier was out of range.
+    InvalidIndex,
+    /// An argument value was out of its valid range or otherwise rejected.
+    InvalidArgument,
+    /// The operation did not complete in time.
+    Timeout,
+    /// The operation was cancelled, like a dialog closed by libvlc.
+    Cancelled,
+    /// Malformed text, like an unexpected libvlc answer, with a description.
+    Parse(String),
+    /// An error at the given line of a file.
+    AtLine(usize, Box<Error>),
+    /// An I/O operation failed.
+    Io(io::ErrorKind),
+    /// LibVLC reported a failure, with its error message if one was set.
+    Vlc(Option<String>),
+}
+
+impl Error {
+    /// Capture (and clear) the LibVLC error message of the calling thread.
+    pub(crate) fn last() -> Error {
+        let msg = errmsg();
+        clearerr();
+        Error::Vlc(msg)
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Error::NulByte => write!(f, "unexpected null byte in string"),
+            Error::InvalidIndex => write!(f, "index out of range"),
+            Error::InvalidArgument => write!(f, "invalid argument"),
+            Error::Timeout => write!(f, "operation timed out"),
+            Error::Cancelled => write!(f, "operation cancelled"),
+            Error::Parse(ref msg) => write!(f, "parse error: {}", msg),
+            Error::AtLine(line, ref error) => write!(f, "line {}: {}", line, error),
+            Error::Io(kind) => write!(f, "I/O error: {:?}", kind),
+            Error::Vlc(Some(ref msg)) => write!(f, "libvlc error: {}", msg),
+            Error::Vlc(None) => write!(f, "libvlc error"),
+        }
+    }
+}
+
+impl error::Error for Error {}
+
+impl From<NulError> for Error {
+    fn from(_: NulError) -> Error {
+        Error::NulByte
+    }
+}
@@ -5,22 +5,49 @@
 extern crate libc;
 
 mod tools;
+mod error;
 mod core;
 mod media;
 mod media_player;
 mod media_list;
+mod media_list_player;
 mod media_library;
+mod media_discoverer;
+mod renderer;
+mod dialog;
 mod enums;
 mod video;
 mod audio;
+mod tracks;
 mod vlm;
+mod vlm_media;
+mod vlm_config;
+mod mrl;
+mod sout;
+#[cfg(feature = "stream")]
+mod stream;
+#[cfg(feature = "async")]
+mod wait;
+#[cfg(any(feature = "log", feature = "tracing"))]
+mod logging;
 
+pub use crate::error::*;
 pub use crate::enums::*;
 pub use crate::core::*;
 pub use crate::media::*;
 pub use crate::media_player::*;
 pub use crate::media_list::*;
+pub use crate::media_list_player::*;
 pub use crate::media_library::*;
+pub use crate::media_discoverer::*;
+pub use crate::renderer::*;
+pub use crate::dialog::*;
 pub use crate::video::*;
 pub use crate::audio::*;
+pub use crate::tracks::*;
 pub use crate::vlm::*;
+pub use crate::vlm_media::*;
+pub use crate::mrl::*;
+pub use crate::sout::*;
+#[cfg(feature = "stream")]
+pub use crate::stream::*;
@@ -0,0 +1,129 @@
+// Copyright (c) 2015 T. Okubo
+// This file is part of vlc-rs.
+// Licensed under the MIT license, see the LICENSE file.
+
+// Adapters forwarding libvlc log messages to the `log` and `tracing` ecosystems.
+// Messages are emitted with the target "vlc::<module>", or "vlc" if the module is unknown.
+
+use crate::{Instance, Log};
+
+fn target(ctx: &Log) -> String {
+    match ctx.module() {
+        Some(module) => format!("vlc::{}", module),
+        None => "vlc".to_owned(),
+    }
+}
+
+#[cfg(feature = "log")]
+impl Instance {
+    /// Forward libvlc log messages to the `log` crate, replacing any previous callback.
+    /// Notices are logged at the Info level.
+    pub fn forward_to_log(&self) {
+        use crate::LogLevel;
+
+        self.set_log(|level, ctx, msg| {
+            let level = match level {
+                LogLevel::Debug => log::Level::Debug,
+                LogLevel::Notice => log::Level::Info,
+                LogLevel::Warning => log::Level::Warn,
+                LogLevel::Error => log::Level::Error,
+            };
+            let target = target(&ctx);
+            let logger = log::logger();
+            if !logger.enabled(&log::Metadata::builder().level(level).target(&target).build()) {
+                return;
+            }
+
+            let module = ctx.module();
+            let file = ctx.file();
+            logger.log(&log::Record::builder()
+                .args(format_args!("{}", msg))
+                .level(level)
+                .target(&target)
+                .module_path(module.as_deref())
+                .file(file.as_deref())
+                .line(ctx.line())
+                .build());
+        });
+    }
+}
+
+#[cfg(feature = "tracing")]
+mod tracing_callsite {
+    use std::collections::HashMap;
+    use std::sync::{Mutex, OnceLock};
+    use tracing_core::{callsite, field, Callsite, Interest, Kind, Level, Metadata};
+
+    pub(super) const FIELDS: &[&str] = &["message", "file", "line", "object"];
+
+    // tracing needs a static callsite per target; one is leaked for each module and level.
+    pub(super) struct LogCallsite {
+        metadata: OnceLock<Metadata<'static>>,
+    }
+
+    impl Callsite for LogCallsite {
+        fn set_interest(&self, _: Interest) {}
+
+        fn metadata(&self) -> &Metadata<'static> {
+            self.metadata.get().expect("callsite metadata is set on creation")
+        }
+    }
+
+    pub(super) fn get(target: String, level: Level) -> &'static LogCallsite {
+        static CALLSITES: OnceLock<Mutex<HashMap<(String, Level), &'static LogCallsite>>> = OnceLock::new();
+
+        let mut callsites = CALLSITES.get_or_init(Default::default).lock().unwrap();
+        callsites.entry((target, level)).or_insert_with_key(|(target, level)| {
+            let cs: &'static LogCallsite = Box::leak(Box::new(LogCallsite{metadata: OnceLock::new()}));
+            let target: &'static str = Box::leak(target.clone().into_boxed_str());
+            let _ = cs.metadata.set(Metadata::new(
+                "vlc log", target, *level, None, None, None,
+                field::FieldSet::new(FIELDS, callsite::Identifier(cs)), Kind::EVENT));
+            callsite::register(cs);
+            cs
+        })
+    }
+}
+
+#[cfg(feature = "tracing")]
+impl Instance {
+    /// Forward libvlc log messages to `tracing` events, replacing any previous callback.
+    /// Notices are emitted at the Info level; the source file and line, and the type of the
+    /// emitting object are recorded as fields.
+    pub fn forward_to_tracing(&self) {
+        use tracing_core::{dispatcher, field::Value, Callsite, Event, Level};
+        use crate::LogLevel;
+
+        self.set_log(|level, ctx, msg| {
+            let level = match level {
+                LogLevel::Debug => Level::DEBUG,
+                LogLevel::Notice => Level::INFO,
+                LogLevel::Warning => Level::WARN,
+                LogLevel::Error => Level::ERROR,
+            };
+            let metadata = tracing_callsite::get(target(&ctx), level).metadata();
+
+            dispatcher::get_default(|dispatch| {
+                if !dispatch.enabled(metadata) {
+                    return;
+                }
+
+                let file = ctx.file();
+                let line = ctx.line();
+                let object = ctx.object_type();
+                let fields = metadata.fields();
+                let mut names = fields.iter();
+                let (f_message, f_file, f_line, f_object) =
+                    (names.next().unwrap(), names.next().unwrap(), names.next().unwrap(), names.next().unwrap());
+                let message = format_args!("{}", msg);
+                let values: [(&_, Option<&dyn Value>); 4] = [
+                    (&f_message, Some(&message)),
+                    (&f_file, file.as_ref().map(|v| v as &dyn Value)),
+                    (&f_line, line.as_ref().map(|v| v as &dyn Value)),
+                    (&f_object, object.as_ref().map(|v| v as &dyn Value)),
+                ];
+                Event::dispatch(metadata, &fields.value_set(&values));
+            });
+        });
+    }
+}
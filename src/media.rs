@@ -3,10 +3,17 @@
 // Licensed under the MIT license, see the LICENSE file.
 
 use vlc_sys as sys;
-use crate::{Instance, EventManager};
-use crate::enums::{State, Meta, TrackType};
+use crate::{Instance, EventManager, Event, EventType, Error};
+use crate::enums::{State, Meta, TrackType, MediaSlaveType, ParseFlag, ParsedStatus};
 use crate::tools::{to_cstr, from_cstr, path_to_cstr};
 use std::path::Path;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::sync::{Arc, Mutex};
+use std::sync::mpsc::channel;
+use std::time::Duration;
+use std::slice;
+use std::fmt;
+use libc::{c_void, c_int, c_uchar};
 
 pub struct Media {
     pub(crate) ptr: *mut sys::libvlc_media_t,
@@ -16,44 +23,95 @@ unsafe impl Send for Media {}
 
 impl Media {
     /// Create a media with a certain given media resource location, for instance a valid URL.
-    pub fn new_location(instance: &Instance, mrl: &str) -> Option<Media> {
-        let cstr = to_cstr(mrl);
+    /// See `Mrl` to build one from parts or from a local path.
+    pub fn new_location(instance: &Instance, mrl: &str) -> Result<Media, Error> {
+        let cstr = to_cstr(mrl)?;
 
         unsafe{
             let p = sys::libvlc_media_new_location(instance.ptr, cstr.as_ptr());
             if p.is_null() {
-                return None;
+                return Err(Error::last());
             }
 
-            Some(Media{ptr: p})
+            Ok(Media{ptr: p})
         }
     }
 
     /// Create a media for a certain file path.
-    pub fn new_path<T: AsRef<Path>>(instance: &Instance, path: T) -> Option<Media> {
-        let cstr = match path_to_cstr(path.as_ref()) {
-            Ok(s) => s,
-            Err(_) => { return None; },
-        };
+    pub fn new_path<T: AsRef<Path>>(instance: &Instance, path: T) -> Result<Media, Error> {
+        let cstr = path_to_cstr(path.as_ref())?;
 
         unsafe{
             let p = sys::libvlc_media_new_path(instance.ptr, cstr.as_ptr());
             if p.is_null() {
-                return None;
+                return Err(Error::last());
             }
 
-            Some(Media{ptr: p})
+            Ok(Media{ptr: p})
         }
     }
 
-    pub fn new_fd(instance: &Instance, fd: i32) -> Option<Media> {
+    pub fn new_fd(instance: &Instance, fd: i32) -> Result<Media, Error> {
         unsafe{
             let p = sys::libvlc_media_new_fd(instance.ptr, fd);
             if p.is_null() {
-                return None;
+                return Err(Error::last());
+            }
+
+            Ok(Media{ptr: p})
+        }
+    }
+
+    /// Create a media reading its data from a seekable reader.
+    /// The stream starts at the current position of the reader, and every time libvlc
+    /// opens the media (to parse, then play it) the reader seeks back there.
+    ///
+    /// The reader is dropped when the media is freed.
+    pub fn new_reader<R>(instance: &Instance, reader: R) -> Result<Media, Error>
+        where R: Read + Seek + Send + 'static
+    {
+        Media::new_callbacks(instance, Box::new(SeekableInput{reader, base: None}), true)
+    }
+
+    /// Create a media reading its data from a reader which does not support seeking.
+    ///
+    /// The stream can only be read once, so the media can be opened by libvlc only once:
+    /// parsing it uses the data up and it can not be played afterwards.
+    /// The reader is dropped when the media is freed.
+    pub fn new_reader_unseekable<R>(instance: &Instance, reader: R) -> Result<Media, Error>
+        where R: Read + Send + 'static
+    {
+        Media::new_callbacks(instance, Box::new(StreamInput{reader, opened: false}), false)
+    }
+
+    fn new_callbacks(instance: &Instance, input: Box<dyn MediaInput>, seekable: bool) -> Result<Media, Error> {
+        let slot: *mut InputSlot = Box::into_raw(Box::new(Arc::new(Mutex::new(Some(input)))));
+
+        unsafe{
+            let p = sys::libvlc_media_new_callbacks(
+                instance.ptr,
+                Some(media_cb_open),
+                Some(media_cb_read),
+                if seekable {Some(media_cb_seek)} else {None},
+                Some(media_cb_close),
+                slot as *mut c_void);
+            if p.is_null() {
+                drop(Box::from_raw(slot));
+                return Err(Error::last());
             }
 
-            Some(Media{ptr: p})
+            // The slot must live as long as libvlc may open the media.
+            let em = sys::libvlc_media_event_manager(p);
+            if sys::libvlc_event_attach(
+                em, sys::libvlc_event_e_libvlc_MediaFreed as i32, Some(media_cb_freed), slot as *mut c_void) != 0 {
+                let err = Error::last();
+                // Not opened yet, so libvlc no longer uses the slot once the media is released.
+                sys::libvlc_media_release(p);
+                drop(Box::from_raw(slot));
+                return Err(err);
+            }
+
+            Ok(Media{ptr: p})
         }
     }
 
@@ -87,10 +145,12 @@ impl Media {
 
     /// Set the meta of the media.
     /// (This function will not save the meta, call save_meta in order to save the meta)
-    pub fn set_meta(&self, meta: Meta, value: &str) {
+    pub fn set_meta(&self, meta: Meta, value: &str) -> Result<(), Error> {
+        let cstr = to_cstr(value)?;
         unsafe{
-            sys::libvlc_media_set_meta(self.ptr, meta as u32, to_cstr(value).as_ptr());
+            sys::libvlc_media_set_meta(self.ptr, meta as u32, cstr.as_ptr());
         }
+        Ok(())
     }
 
     /// Save the meta previously set.
@@ -100,7 +160,7 @@ impl Media {
 
     /// Get current state of media descriptor object.
     pub fn state(&self) -> State {
-        unsafe{ sys::libvlc_media_get_state(self.ptr).into() }
+        State::from_raw(unsafe{ sys::libvlc_media_get_state(self.ptr) }).unwrap_or(State::NothingSpecial)
     }
 
     /// Get duration (in ms) of media descriptor object item.
@@ -112,13 +172,60 @@ impl Media {
     }
 
     /// Parse a media.
+    /// This call blocks; it is deprecated by libvlc in favour of parse_with_options().
     pub fn parse(&self) {
         unsafe{ sys::libvlc_media_parse(self.ptr) };
     }
 
-    /// Parse a media.
-    pub fn parse_async(&self) {
-        unsafe{ sys::libvlc_media_parse_async(self.ptr) };
+    /// Parse the media asynchronously with options.
+    /// The flags are combined; an empty slice parses local media only.
+    /// `timeout` of None uses the default preparse timeout. Other timeouts are rounded up
+    /// to at least 1 ms, as libvlc takes 0 as no timeout at all.
+    /// Completion is notified by a MediaParsedChanged event; see also parse_and_wait().
+    /// A media is only parsed once, later calls have no effect.
+    pub fn parse_with_options(&self, flags: &[ParseFlag], timeout: Option<Duration>) -> Result<(), Error> {
+        let flags = flags.iter().fold(0, |acc, &flag| acc | flag as u32);
+        let timeout = timeout.map_or(-1, |t| t.as_millis().clamp(1, i32::MAX as u128) as i32);
+        unsafe{
+            if sys::libvlc_media_parse_with_options(self.ptr, flags, timeout) == 0 { Ok(()) }else{ Err(Error::last()) }
+        }
+    }
+
+    /// Stop the parsing of the media.
+    /// A MediaParsedChanged event with the Timeout status is sent if parsing was in progress.
+    pub fn parse_stop(&self) {
+        unsafe{ sys::libvlc_media_parse_stop(self.ptr) };
+    }
+
+    /// Get the parsed status of the media, or None if it has not been parsed yet.
+    pub fn parsed_status(&self) -> Option<ParsedStatus> {
+        let status = unsafe{ sys::libvlc_media_get_parsed_status(self.ptr) };
+        if status == 0 { None }else{ Some(ParsedStatus::from_raw(status).unwrap_or(ParsedStatus::Failed)) }
+    }
+
+    /// Parse the media and block until parsing finishes or `timeout` expires.
+    /// Parsing is stopped and Timeout returned if it takes longer than `timeout`.
+    /// If the media was already parsed, its status is returned immediately.
+    pub fn parse_and_wait(&self, flags: &[ParseFlag], timeout: Duration) -> Result<ParsedStatus, Error> {
+        let (tx, rx) = channel();
+        let em = self.event_manager();
+        let _subscription = em.attach(EventType::MediaParsedChanged, move |e, _| {
+            if let Event::MediaParsedChanged(status) = e {
+                let _ = tx.send(status);
+            }
+        })?;
+
+        self.parse_with_options(flags, Some(timeout))?;
+        if let Some(status) = self.parsed_status() {
+            return Ok(status);
+        }
+        match rx.recv_timeout(timeout) {
+            Ok(status) if status > 0 => Ok(ParsedStatus::from_raw(status as u32).unwrap_or(ParsedStatus::Failed)),
+            _ => {
+                self.parse_stop();
+                Ok(ParsedStatus::Timeout)
+            },
+        }
     }
 
     /// Get Parsed status for media descriptor object.
@@ -138,7 +245,7 @@ impl Media {
 
             for i in 0..n {
                 let p = p_track.offset(i as isize);
-                let i_type: TrackType = (**p).i_type.into();
+                let i_type = TrackType::from_raw((**p).i_type).unwrap_or(TrackType::Unknown);
                 let type_specific_data = match i_type {
                     TrackType::Audio => {
                         let audio = (**p).__bindgen_anon_1.audio;
@@ -170,7 +277,7 @@ impl Media {
                     codec:              (**p).i_codec,
                     original_fourcc:    (**p).i_original_fourcc,
                     id:                 (**p).i_id,
-                    track_type:         (**p).i_type.into(),
+                    track_type:         i_type,
                     profile:            (**p).i_profile,
                     level:              (**p).i_level,
                     bitrate:            (**p).i_bitrate,
@@ -193,11 +300,59 @@ impl Media {
     /// - `:input-fast-seek` - disable fast seeking for more accurate time-based seeks
     /// - `:avcodec-hurry-up=0` - disable frame skipping for precise decoding
     /// - `:file-caching=3000` - set caching (in ms)
-    pub fn add_option(&self, option: &str) {
+    pub fn add_option(&self, option: &str) -> Result<(), Error> {
+        let cstr = to_cstr(option)?;
         unsafe{
-            let cstr = to_cstr(option);
             sys::libvlc_media_add_option(self.ptr, cstr.as_ptr());
         }
+        Ok(())
+    }
+
+    /// Add a slave to the current media.
+    /// A slave is an external input source that may contains an additional subtitle track
+    /// (like a .srt) or an additional audio track (like a .ac3).
+    /// `priority` goes from 0 (lowest) to `MEDIA_SLAVE_MAX_PRIORITY` (highest, used for
+    /// slaves added by the user). This function must be called before the media is parsed
+    /// or before the media is played.
+    pub fn add_slave(&self, kind: MediaSlaveType, priority: u32, uri: &str) -> Result<(), Error> {
+        if priority > MEDIA_SLAVE_MAX_PRIORITY {
+            return Err(Error::InvalidArgument);
+        }
+        let cstr = to_cstr(uri)?;
+        unsafe{
+            if sys::libvlc_media_slaves_add(self.ptr, kind as u32, priority, cstr.as_ptr()) == 0 { Ok(()) }else{ Err(Error::last()) }
+        }
+    }
+
+    /// Clear all slaves previously added by add_slave() or internally.
+    pub fn clear_slaves(&self) {
+        unsafe{ sys::libvlc_media_slaves_clear(self.ptr) };
+    }
+
+    /// Get the media descriptor's slaves.
+    /// Slaves are added by add_slave() or internally by libvlc when the media is parsed
+    /// or played.
+    pub fn slaves(&self) -> Vec<MediaSlave> {
+        unsafe{
+            let mut p_slaves: *mut *mut sys::libvlc_media_slave_t = ::std::ptr::null_mut();
+            let n = sys::libvlc_media_slaves_get(self.ptr, &mut p_slaves);
+            if n == 0 {
+                return Vec::new();
+            }
+
+            // Slaves of a type unknown to vlc-rs are skipped.
+            let slaves = (0..n as usize).filter_map(|i| {
+                let p = *p_slaves.add(i);
+                Some(MediaSlave{
+                    kind:     MediaSlaveType::from_raw((*p).i_type)?,
+                    priority: (*p).i_priority,
+                    uri:      from_cstr((*p).psz_uri).unwrap_or_default(),
+                })
+            }).collect();
+
+            sys::libvlc_media_slaves_release(p_slaves, n);
+            slaves
+        }
     }
 
     /// Returns raw pointer
@@ -206,12 +361,154 @@ impl Media {
     }
 }
 
+impl Clone for Media {
+    /// Take a new reference on the same media.
+    fn clone(&self) -> Media {
+        unsafe{ sys::libvlc_media_retain(self.ptr) };
+        Media{ptr: self.ptr}
+    }
+}
+
+impl fmt::Debug for Media {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Media").field("mrl", &self.mrl()).finish()
+    }
+}
+
 impl Drop for Media {
     fn drop(&mut self) {
         unsafe{ sys::libvlc_media_release(self.ptr) };
     }
 }
 
+// Reader behind a media created by Media::new_reader, shared with the callbacks below.
+trait MediaInput: Send {
+    // Prepare reading from the start of the stream and return its size if known.
+    fn open(&mut self) -> io::Result<Option<u64>>;
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize>;
+    fn seek(&mut self, offset: u64) -> io::Result<()>;
+}
+
+struct SeekableInput<R> {
+    reader: R,
+    // Start of the stream, taken on the first open.
+    base: Option<u64>,
+}
+
+impl<R: Read + Seek + Send> MediaInput for SeekableInput<R> {
+    fn open(&mut self) -> io::Result<Option<u64>> {
+        let base = match self.base {
+            Some(base) => base,
+            None => self.reader.stream_position()?,
+        };
+        self.base = Some(base);
+        let end = self.reader.seek(SeekFrom::End(0))?;
+        self.reader.seek(SeekFrom::Start(base))?;
+        Ok(Some(end.saturating_sub(base)))
+    }
+
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.reader.read(buf)
+    }
+
+    fn seek(&mut self, offset: u64) -> io::Result<()> {
+        self.reader.seek(SeekFrom::Start(self.base.unwrap_or(0) + offset)).map(|_| ())
+    }
+}
+
+struct StreamInput<R> {
+    reader: R,
+    opened: bool,
+}
+
+impl<R: Read + Send> MediaInput for StreamInput<R> {
+    fn open(&mut self) -> io::Result<Option<u64>> {
+        // The data read by a previous open is gone.
+        if self.opened {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        self.opened = true;
+        Ok(None)
+    }
+
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.reader.read(buf)
+    }
+
+    fn seek(&mut self, _offset: u64) -> io::Result<()> {
+        Err(io::ErrorKind::Other.into())
+    }
+}
+
+// Holds the reader while libvlc does not have the media open.
+type InputSlot = Arc<Mutex<Option<Box<dyn MediaInput>>>>;
+
+// Reader of an open media, given back to its slot on close.
+struct OpenInput {
+    slot: InputSlot,
+    input: Box<dyn MediaInput>,
+}
+
+unsafe extern "C" fn media_cb_open(opaque: *mut c_void, datap: *mut *mut c_void, sizep: *mut u64) -> c_int {
+    let slot = &*(opaque as *const InputSlot);
+    let mut guard = slot.lock().unwrap();
+    // Already open, the reader can't be shared.
+    let mut input = match guard.take() {
+        Some(input) => input,
+        None => return -1,
+    };
+
+    match input.open() {
+        Ok(size) => {
+            *sizep = size.unwrap_or(u64::MAX);
+            *datap = Box::into_raw(Box::new(OpenInput{slot: slot.clone(), input})) as *mut c_void;
+            0
+        },
+        Err(_) => {
+            *guard = Some(input);
+            -1
+        },
+    }
+}
+
+unsafe extern "C" fn media_cb_read(opaque: *mut c_void, buf: *mut c_uchar, len: sys::size_t) -> sys::ssize_t {
+    let input = &mut (*(opaque as *mut OpenInput)).input;
+    let buf = slice::from_raw_parts_mut(buf, len as usize);
+
+    loop {
+        match input.read(buf) {
+            Ok(n) => return n as sys::ssize_t,
+            Err(ref e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(_) => return -1,
+        }
+    }
+}
+
+unsafe extern "C" fn media_cb_seek(opaque: *mut c_void, offset: u64) -> c_int {
+    let input = &mut (*(opaque as *mut OpenInput)).input;
+    if input.seek(offset).is_ok() { 0 }else{ -1 }
+}
+
+unsafe extern "C" fn media_cb_close(opaque: *mut c_void) {
+    let open = Box::from_raw(opaque as *mut OpenInput);
+    *open.slot.lock().unwrap() = Some(open.input);
+}
+
+unsafe extern "C" fn media_cb_freed(_pe: *const sys::libvlc_event_t, data: *mut c_void) {
+    drop(Box::from_raw(data as *mut InputSlot));
+}
+
+/// Highest priority of a media slave, used for slaves added by the user.
+pub const MEDIA_SLAVE_MAX_PRIORITY: u32 = 4;
+
+/// An external subtitle or audio input attached to a media.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct MediaSlave {
+    pub kind: MediaSlaveType,
+    pub priority: u32,
+    pub uri: String,
+}
+
 #[derive(Clone, PartialEq, Eq, Hash, Debug)]
 pub struct MediaTrack {
     pub codec: u32,
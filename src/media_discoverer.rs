@@ -0,0 +1,115 @@
+// Copyright (c) 2015 T. Okubo
+// This file is part of vlc-rs.
+// Licensed under the MIT license, see the LICENSE file.
+
+use vlc_sys as sys;
+use crate::{Instance, MediaList, EventManager, Error};
+use crate::enums::DiscovererCategory;
+use crate::tools::{to_cstr, from_cstr};
+use std::ptr;
+use std::slice;
+
+/// Media discoverer, finding media from a service like UPnP, SAP or the local directories.
+/// Discovered media are added to its media list.
+pub struct MediaDiscoverer {
+    pub(crate) ptr: *mut sys::libvlc_media_discoverer_t,
+}
+
+unsafe impl Send for MediaDiscoverer {}
+
+impl MediaDiscoverer {
+    /// Create a media discoverer for the service with the given name.
+    /// The names are given by `Instance::media_discoverer_list_get`.
+    /// The discoverer has to be started with start().
+    pub fn new(instance: &Instance, name: &str) -> Result<MediaDiscoverer, Error> {
+        let cstr = to_cstr(name)?;
+        unsafe{
+            let p = sys::libvlc_media_discoverer_new(instance.ptr, cstr.as_ptr());
+            if p.is_null() { Err(Error::last()) }else{ Ok(MediaDiscoverer{ptr: p}) }
+        }
+    }
+
+    /// Start media discovery.
+    pub fn start(&self) -> Result<(), Error> {
+        unsafe{
+            if sys::libvlc_media_discoverer_start(self.ptr) == 0 { Ok(()) }else{ Err(Error::last()) }
+        }
+    }
+
+    /// Stop media discovery.
+    pub fn stop(&self) {
+        unsafe{ sys::libvlc_media_discoverer_stop(self.ptr) };
+    }
+
+    /// Query if media discovery is running.
+    pub fn is_running(&self) -> bool {
+        unsafe{ sys::libvlc_media_discoverer_is_running(self.ptr) != 0 }
+    }
+
+    /// Get the media list of discovered media.
+    /// Its events notify the media added and removed by the service.
+    pub fn media_list(&self) -> Option<MediaList> {
+        unsafe{
+            let p = sys::libvlc_media_discoverer_media_list(self.ptr);
+            if p.is_null() { None }else{ Some(MediaList{ptr: p}) }
+        }
+    }
+
+    /// Get the localized name of the service.
+    pub fn localized_name(&self) -> Option<String> {
+        unsafe{
+            let p = sys::libvlc_media_discoverer_localized_name(self.ptr);
+            let s = from_cstr(p);
+            if !p.is_null() { sys::libvlc_free(p as *mut libc::c_void); }
+            s
+        }
+    }
+
+    /// Get the event manager of the media discoverer.
+    pub fn event_manager<'a>(&'a self) -> EventManager<'a> {
+        unsafe{
+            let p = sys::libvlc_media_discoverer_event_manager(self.ptr);
+            assert!(!p.is_null());
+            EventManager{ptr: p, _phantomdata: ::std::marker::PhantomData}
+        }
+    }
+
+    /// Returns raw pointer
+    pub fn raw(&self) -> *mut sys::libvlc_media_discoverer_t {
+        self.ptr
+    }
+}
+
+impl Drop for MediaDiscoverer {
+    fn drop(&mut self) {
+        unsafe{ sys::libvlc_media_discoverer_release(self.ptr) };
+    }
+}
+
+/// Description of a media discovery service.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct MediaDiscovererDescription {
+    /// Name to pass to MediaDiscoverer::new().
+    pub name: String,
+    /// Human readable name.
+    pub long_name: String,
+    pub category: DiscovererCategory,
+}
+
+pub(crate) unsafe fn media_discoverer_list(instance: &Instance, category: DiscovererCategory) -> Vec<MediaDiscovererDescription> {
+    let mut services = ptr::null_mut();
+    let count = sys::libvlc_media_discoverer_list_get(instance.ptr, category as u32, &mut services);
+    if count == 0 || services.is_null() {
+        return Vec::new();
+    }
+
+    let list = slice::from_raw_parts(services, count as usize).iter().filter_map(|&p| {
+        Some(MediaDiscovererDescription{
+            name: from_cstr((*p).psz_name)?,
+            long_name: from_cstr((*p).psz_longname).unwrap_or_default(),
+            category: DiscovererCategory::from_raw((*p).i_cat)?,
+        })
+    }).collect();
+    sys::libvlc_media_discoverer_list_release(services, count);
+    list
+}
@@ -3,7 +3,7 @@
 // Licensed under the MIT license, see the LICENSE file.
 
 use vlc_sys as sys;
-use crate::{Instance, MediaList};
+use crate::{Instance, MediaList, Error};
 
 pub struct MediaLibrary {
     pub(crate) ptr: *mut sys::libvlc_media_library_t,
@@ -11,17 +11,17 @@ pub struct MediaLibrary {
 
 impl MediaLibrary {
     /// Create an new Media Library object.
-    pub fn new(instance: &Instance) -> Option<MediaLibrary> {
+    pub fn new(instance: &Instance) -> Result<MediaLibrary, Error> {
         unsafe{
             let p = sys::libvlc_media_library_new(instance.ptr);
-            if p.is_null() { None }else{ Some(MediaLibrary{ptr: p}) }
+            if p.is_null() { Err(Error::last()) }else{ Ok(MediaLibrary{ptr: p}) }
         }
     }
 
     /// Load media library.
-    pub fn load(&self) -> Result<(), ()> {
+    pub fn load(&self) -> Result<(), Error> {
         unsafe{
-            if sys::libvlc_media_library_load(self.ptr) == 0 { Ok(()) }else{ Err(()) }
+            if sys::libvlc_media_library_load(self.ptr) == 0 { Ok(()) }else{ Err(Error::last()) }
         }
     }
 
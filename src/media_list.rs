@@ -3,7 +3,7 @@
 // Licensed under the MIT license, see the LICENSE file.
 
 use vlc_sys as sys;
-use crate::{Instance, Media, EventManager};
+use crate::{Instance, Media, EventManager, Error};
 
 pub struct MediaList {
     pub(crate) ptr: *mut sys::libvlc_media_list_t,
@@ -11,10 +11,10 @@ pub struct MediaList {
 
 impl MediaList {
     /// Create an empty media list.
-    pub fn new(instance: &Instance) -> Option<MediaList> {
+    pub fn new(instance: &Instance) -> Result<MediaList, Error> {
         unsafe{
             let p = sys::libvlc_media_list_new(instance.ptr);
-            if p.is_null() { None }else{ Some(MediaList{ptr: p}) }
+            if p.is_null() { Err(Error::last()) }else{ Ok(MediaList{ptr: p}) }
         }
     }
 
@@ -35,25 +35,31 @@ impl MediaList {
 
     /// Add media instance to media list.
     /// The MediaList::lock should be held upon entering this function.
-    pub fn add_media(&self, md: &Media) -> Result<(), ()> {
+    pub fn add_media(&self, md: &Media) -> Result<(), Error> {
         unsafe{
-            if sys::libvlc_media_list_add_media(self.ptr, md.ptr) == 0 { Ok(()) }else{ Err(()) }
+            if sys::libvlc_media_list_add_media(self.ptr, md.ptr) == 0 { Ok(()) }else{ Err(Error::last()) }
         }
     }
 
     /// Insert media instance in media list on a position.
     /// The MediaList::lock should be held upon entering this function.
-    pub fn insert_media(&self, md: &Media, pos: i32) -> Result<(), ()> {
+    pub fn insert_media(&self, md: &Media, pos: i32) -> Result<(), Error> {
+        if pos < 0 || pos > self.count() {
+            return Err(Error::InvalidIndex);
+        }
         unsafe{
-            if sys::libvlc_media_list_insert_media(self.ptr, md.ptr, pos) == 0 { Ok(()) }else{ Err(()) }
+            if sys::libvlc_media_list_insert_media(self.ptr, md.ptr, pos) == 0 { Ok(()) }else{ Err(Error::last()) }
         }
     }
 
     /// Remove media instance from media list on a position.
     /// The MediaList::lock should be held upon entering this function.
-    pub fn remove_index(&self, pos: i32) -> Result<(), ()> {
+    pub fn remove_index(&self, pos: i32) -> Result<(), Error> {
+        if pos < 0 || pos >= self.count() {
+            return Err(Error::InvalidIndex);
+        }
         unsafe{
-            if sys::libvlc_media_list_remove_index(self.ptr, pos) == 0 { Ok(()) }else{ Err(()) }
+            if sys::libvlc_media_list_remove_index(self.ptr, pos) == 0 { Ok(()) }else{ Err(Error::last()) }
         }
     }
 
@@ -0,0 +1,144 @@
+// Copyright (c) 2015 T. Okubo
+// This file is part of vlc-rs.
+// Licensed under the MIT license, see the LICENSE file.
+
+use vlc_sys as sys;
+use crate::{Instance, Media, MediaList, MediaPlayer, EventManager, Error};
+use crate::enums::{State, PlaybackMode};
+use crate::media_player::{SharedCallbacks, player_callbacks, release_player};
+use std::cell::RefCell;
+
+/// A LibVLC media list player plays a MediaList (usually in a custom drawable)
+/// through an underlying MediaPlayer.
+pub struct MediaListPlayer {
+    pub(crate) ptr: *mut sys::libvlc_media_list_player_t,
+    // Keeps the callbacks of the player alive while the media list player uses it.
+    player_callbacks: RefCell<SharedCallbacks>,
+}
+
+unsafe impl Send for MediaListPlayer {}
+
+impl MediaListPlayer {
+    /// Create new media_list_player.
+    pub fn new(instance: &Instance) -> Result<MediaListPlayer, Error> {
+        unsafe{
+            let p = sys::libvlc_media_list_player_new(instance.ptr);
+            if p.is_null() {
+                return Err(Error::last());
+            }
+            let mp = sys::libvlc_media_list_player_get_media_player(p);
+            let player_callbacks = RefCell::new(player_callbacks(mp));
+            sys::libvlc_media_player_release(mp);
+            Ok(MediaListPlayer{ptr: p, player_callbacks})
+        }
+    }
+
+    /// Get the Event Manager from which the media list player send event.
+    pub fn event_manager<'a>(&'a self) -> EventManager<'a> {
+        unsafe{
+            let p = sys::libvlc_media_list_player_event_manager(self.ptr);
+            assert!(!p.is_null());
+            EventManager{ptr: p, _phantomdata: ::std::marker::PhantomData}
+        }
+    }
+
+    /// Replace media player in media_list_player with this instance.
+    /// The media list player keeps its own reference, so `mp` may be dropped afterwards.
+    pub fn set_media_player(&self, mp: &MediaPlayer) {
+        let previous = self.player_callbacks.replace((*mp.callbacks).clone());
+        release_player(|| unsafe{ sys::libvlc_media_list_player_set_media_player(self.ptr, mp.ptr) }, previous);
+    }
+
+    /// Get media player of the media_list_player instance.
+    pub fn get_media_player(&self) -> Option<MediaPlayer> {
+        unsafe{
+            let p = sys::libvlc_media_list_player_get_media_player(self.ptr);
+            if p.is_null() { None }else{ Some(MediaPlayer::from_ptr(p)) }
+        }
+    }
+
+    /// Set the media list associated with the player.
+    /// The media list player keeps its own reference, so `mlist` may be dropped afterwards.
+    pub fn set_media_list(&self, mlist: &MediaList) {
+        unsafe{ sys::libvlc_media_list_player_set_media_list(self.ptr, mlist.ptr); }
+    }
+
+    /// Play media list.
+    pub fn play(&self) {
+        unsafe{ sys::libvlc_media_list_player_play(self.ptr); }
+    }
+
+    /// Toggle pause (or resume) media list.
+    pub fn pause(&self) {
+        unsafe{ sys::libvlc_media_list_player_pause(self.ptr); }
+    }
+
+    /// Pause or resume media list.
+    pub fn set_pause(&self, do_pause: bool) {
+        unsafe{ sys::libvlc_media_list_player_set_pause(self.ptr, if do_pause {1} else {0}); }
+    }
+
+    /// Is media list playing?
+    pub fn is_playing(&self) -> bool {
+        unsafe{ sys::libvlc_media_list_player_is_playing(self.ptr) != 0 }
+    }
+
+    /// Get current libvlc_state of media list player.
+    pub fn state(&self) -> State {
+        State::from_raw(unsafe{ sys::libvlc_media_list_player_get_state(self.ptr) }).unwrap_or(State::NothingSpecial)
+    }
+
+    /// Play media list item at position index.
+    pub fn play_item_at_index(&self, index: i32) -> Result<(), Error> {
+        if index < 0 {
+            return Err(Error::InvalidIndex);
+        }
+        unsafe{
+            if sys::libvlc_media_list_player_play_item_at_index(self.ptr, index) == 0 { Ok(()) }else{ Err(Error::last()) }
+        }
+    }
+
+    /// Play the given media item.
+    /// The media has to be part of the media list set with set_media_list().
+    pub fn play_item(&self, md: &Media) -> Result<(), Error> {
+        unsafe{
+            if sys::libvlc_media_list_player_play_item(self.ptr, md.ptr) == 0 { Ok(()) }else{ Err(Error::last()) }
+        }
+    }
+
+    /// Stop playing media list.
+    pub fn stop(&self) {
+        unsafe{ sys::libvlc_media_list_player_stop(self.ptr); }
+    }
+
+    /// Play next item from media list.
+    pub fn next(&self) -> Result<(), Error> {
+        unsafe{
+            if sys::libvlc_media_list_player_next(self.ptr) == 0 { Ok(()) }else{ Err(Error::last()) }
+        }
+    }
+
+    /// Play previous item from media list.
+    pub fn previous(&self) -> Result<(), Error> {
+        unsafe{
+            if sys::libvlc_media_list_player_previous(self.ptr) == 0 { Ok(()) }else{ Err(Error::last()) }
+        }
+    }
+
+    /// Sets the playback mode for the playlist.
+    pub fn set_playback_mode(&self, mode: PlaybackMode) {
+        unsafe{ sys::libvlc_media_list_player_set_playback_mode(self.ptr, mode as u32); }
+    }
+
+    /// Returns raw pointer
+    pub fn raw(&self) -> *mut sys::libvlc_media_list_player_t {
+        self.ptr
+    }
+}
+
+impl Drop for MediaListPlayer {
+    fn drop(&mut self) {
+        let callbacks = self.player_callbacks.replace(SharedCallbacks::default());
+        release_player(|| unsafe{ sys::libvlc_media_list_player_release(self.ptr) }, callbacks);
+    }
+}
@@ -6,30 +6,45 @@ use vlc_sys as sys;
 use crate::Instance;
 use crate::Media;
 use crate::EventManager;
+use crate::Error;
+use crate::RendererItem;
+use crate::tools::{to_cstr, from_cstr};
 use libc::{c_void, c_uint};
-use crate::enums::{State, Position};
-use std::mem::transmute;
+use crate::enums::{State, Position, MediaSlaveType};
+use crate::video::{
+    VideoFormat, VideoFrame, VideoCallbacksData, VideoLockCb, VideoCleanupCb,
+    video_cb_format, video_cb_cleanup, video_cb_lock, video_cb_unlock, video_cb_display,
+};
+use std::mem::{transmute, ManuallyDrop};
+use std::ptr;
+use std::sync::{Arc, Mutex, Weak};
 
 /// A LibVLC media player plays one media (usually in a custom drawable).
 pub struct MediaPlayer {
     pub(crate) ptr: *mut sys::libvlc_media_player_t,
+    pub(crate) callbacks: ManuallyDrop<SharedCallbacks>,
 }
 
 unsafe impl Send for MediaPlayer {}
 
 impl MediaPlayer {
     /// Create an empty Media Player object
-    pub fn new(instance: &Instance) -> Option<MediaPlayer> {
+    pub fn new(instance: &Instance) -> Result<MediaPlayer, Error> {
         unsafe{
             let p = sys::libvlc_media_player_new(instance.ptr);
 
             if p.is_null() {
-                return None;
+                return Err(Error::last());
             }
-            Some(MediaPlayer{ptr: p})
+            Ok(MediaPlayer::from_ptr(p))
         }
     }
 
+    // Wrap a player reference owned by the caller.
+    pub(crate) unsafe fn from_ptr(p: *mut sys::libvlc_media_player_t) -> MediaPlayer {
+        MediaPlayer{ptr: p, callbacks: ManuallyDrop::new(player_callbacks(p))}
+    }
+
     /// Set the media that will be used by the media_player. If any, previous md will be released.
     pub fn set_media(&self, md: &Media) {
         unsafe{ sys::libvlc_media_player_set_media(self.ptr, md.ptr) };
@@ -64,11 +79,11 @@ impl MediaPlayer {
     }
 
     /// Play
-    pub fn play(&self) -> Result<(), ()> {
+    pub fn play(&self) -> Result<(), Error> {
         if unsafe{ sys::libvlc_media_player_play(self.ptr) } == 0 {
             Ok(())
         }else{
-            Err(())
+            Err(Error::last())
         }
     }
 
@@ -87,6 +102,10 @@ impl MediaPlayer {
         unsafe{ sys::libvlc_media_player_stop(self.ptr) };
     }
 
+    /// Set callbacks to receive decoded audio samples instead of playing them.
+    ///
+    /// The closures stay alive as long as the player: until every handle to it and every
+    /// `MediaListPlayer` using it is dropped, as libvlc may still call them.
     pub fn set_callbacks<F>(
         &self,
         play: F,
@@ -106,6 +125,7 @@ impl MediaPlayer {
             flush: flush, drain: drain,
         };
         let data = Box::into_raw(Box::new(data));
+        self.callbacks.lock().unwrap().audio.push(data);
 
         unsafe{
             sys::libvlc_audio_set_callbacks(
@@ -119,6 +139,69 @@ impl MediaPlayer {
         }
     }
 
+    /// Set callbacks to receive decoded video frames instead of rendering them in a window.
+    ///
+    /// `setup` is called when the video output starts with the format of the source.
+    /// It may change the chroma and dimensions, must fill pitches and lines for each plane
+    /// (see `VideoFormat::set_chroma`) and returns false to refuse the format.
+    /// Frame buffers are allocated by vlc-rs; `lock` receives the planes of a frame
+    /// before it is decoded into, `display` the frame once it is due to be shown.
+    /// `cleanup` is called when the video output stops.
+    ///
+    /// Without `lock` or `cleanup`, pass `None::<fn(&mut [&mut [u8]])>` or `None::<fn()>`.
+    ///
+    /// Must be called before the media is played. The closures, also those registered by a
+    /// previous call, stay alive as long as the player: until every handle to it and every
+    /// `MediaListPlayer` using it is dropped, as a running video output may still use them.
+    pub fn set_video_callbacks<S, L, D, C>(
+        &self,
+        setup: S,
+        lock: Option<L>,
+        display: D,
+        cleanup: Option<C>)
+        where S: FnMut(&mut VideoFormat) -> bool + Send + 'static,
+              L: FnMut(&mut [&mut [u8]]) + Send + 'static,
+              D: FnMut(&VideoFrame) + Send + 'static,
+              C: FnMut() + Send + 'static,
+    {
+        let lock = lock.map(|f| Box::new(f) as VideoLockCb);
+        let cleanup = cleanup.map(|f| Box::new(f) as VideoCleanupCb);
+        let data = VideoCallbacksData::new(Box::new(setup), lock, Box::new(display), cleanup);
+        let data = Box::into_raw(Box::new(data));
+        self.callbacks.lock().unwrap().video.push(data);
+
+        unsafe{
+            sys::libvlc_video_set_format_callbacks(
+                self.ptr, Some(video_cb_format), Some(video_cb_cleanup));
+            sys::libvlc_video_set_callbacks(
+                self.ptr,
+                Some(video_cb_lock),
+                Some(video_cb_unlock),
+                Some(video_cb_display),
+                data as *mut c_void);
+        }
+    }
+
+    /// Add a slave to the current media player.
+    /// If the player is playing, the slave will be added directly. This call
+    /// will also update the slave list of the attached Media.
+    /// If `select` is true, the added slave track is selected.
+    pub fn add_slave(&self, kind: MediaSlaveType, uri: &str, select: bool) -> Result<(), Error> {
+        let cstr = to_cstr(uri)?;
+        unsafe{
+            if sys::libvlc_media_player_add_slave(self.ptr, kind as u32, cstr.as_ptr(), select) == 0 { Ok(()) }else{ Err(Error::last()) }
+        }
+    }
+
+    /// Set a renderer to the media player, or None to render locally again.
+    /// This must be called before playback starts.
+    pub fn set_renderer(&self, item: Option<&RendererItem>) -> Result<(), Error> {
+        let p = item.map_or(ptr::null_mut(), |item| item.ptr);
+        unsafe{
+            if sys::libvlc_media_player_set_renderer(self.ptr, p) == 0 { Ok(()) }else{ Err(Error::last()) }
+        }
+    }
+
     /// Set the NSView handler where the media player should render its video output.
     pub fn set_nsobject(&self, drawable: *mut c_void) {
         unsafe{ sys::libvlc_media_player_set_nsobject(self.ptr, drawable) };
@@ -255,10 +338,10 @@ impl MediaPlayer {
     }
 
     /// Set movie play rate.
-    pub fn set_rate(&self, rate: f32) -> Result<(),()> {
+    pub fn set_rate(&self, rate: f32) -> Result<(), Error> {
         unsafe{
             if sys::libvlc_media_player_set_rate(self.ptr, rate) == -1 {
-                Err(())
+                Err(Error::last())
             }else{
                 Ok(())
             }
@@ -267,7 +350,7 @@ impl MediaPlayer {
 
     /// Get current movie state.
     pub fn state(&self) -> State {
-        unsafe{ sys::libvlc_media_player_get_state(self.ptr) }.into()
+        State::from_raw(unsafe{ sys::libvlc_media_player_get_state(self.ptr) }).unwrap_or(State::NothingSpecial)
     }
 
     /// How many video outputs does this media player have?
@@ -322,7 +405,64 @@ impl MediaPlayer {
 
 impl Drop for MediaPlayer {
     fn drop(&mut self) {
-        unsafe{ sys::libvlc_media_player_release(self.ptr) };
+        let ptr = self.ptr;
+        let callbacks = unsafe{ ManuallyDrop::take(&mut self.callbacks) };
+        release_player(|| unsafe{ sys::libvlc_media_player_release(ptr) }, callbacks);
+    }
+}
+
+// Callback data registered on a libvlc player, freed once no MediaPlayer or
+// MediaListPlayer refers to the player any more.
+#[derive(Default)]
+pub(crate) struct CallbacksData {
+    audio: Vec<*mut AudioCallbacksData>,
+    video: Vec<*mut VideoCallbacksData>,
+}
+
+// The closures are Send.
+unsafe impl Send for CallbacksData {}
+
+pub(crate) type SharedCallbacks = Arc<Mutex<CallbacksData>>;
+
+static PLAYERS: Mutex<Vec<(usize, Weak<Mutex<CallbacksData>>)>> = Mutex::new(Vec::new());
+
+// The callback data of a libvlc player, shared by all the handles to it.
+pub(crate) fn player_callbacks(p: *mut sys::libvlc_media_player_t) -> SharedCallbacks {
+    let mut players = PLAYERS.lock().unwrap();
+    players.retain(|entry| entry.1.strong_count() > 0);
+    if let Some(callbacks) = players.iter().find(|entry| entry.0 == p as usize).and_then(|entry| entry.1.upgrade()) {
+        return callbacks;
+    }
+    let callbacks = SharedCallbacks::default();
+    players.push((p as usize, Arc::downgrade(&callbacks)));
+    callbacks
+}
+
+// Run `release`, which drops a libvlc reference to a player, then drop a reference to
+// its callbacks. The last reference is unregistered first, so that a new player at the
+// same address does not pick the callbacks up; they are freed once libvlc released the player.
+pub(crate) fn release_player<F: FnOnce()>(release: F, callbacks: SharedCallbacks) {
+    {
+        let mut players = PLAYERS.lock().unwrap();
+        release();
+        if Arc::strong_count(&callbacks) == 1 {
+            players.retain(|entry| !ptr::eq(entry.1.as_ptr(), Arc::as_ptr(&callbacks)));
+        }
+    }
+    // Outside of the lock, as dropping the closures may drop other players.
+    drop(callbacks);
+}
+
+impl Drop for CallbacksData {
+    fn drop(&mut self) {
+        unsafe{
+            for &p in &self.audio {
+                drop(Box::from_raw(p));
+            }
+            for &p in &self.video {
+                drop(Box::from_raw(p));
+            }
+        }
     }
 }
 
@@ -368,3 +508,17 @@ pub struct TrackDescription {
     pub name: Option<String>,
 }
 
+// Convert a libvlc track description list to a Vec and release it.
+pub(crate) unsafe fn track_description_list(p0: *mut sys::libvlc_track_description_t) -> Option<Vec<TrackDescription>> {
+    if p0.is_null() { return None; }
+    let mut td = Vec::new();
+    let mut p = p0;
+
+    while !p.is_null() {
+        td.push(TrackDescription{ id: (*p).i_id, name: from_cstr((*p).psz_name) });
+        p = (*p).p_next;
+    }
+    sys::libvlc_track_description_list_release(p0);
+    Some(td)
+}
+
@@ -0,0 +1,182 @@
+// Copyright (c) 2015 T. Okubo
+// This file is part of vlc-rs.
+// Licensed under the MIT license, see the LICENSE file.
+
+use std::env;
+use std::fmt;
+use std::path::Path;
+use crate::Error;
+
+/// Builder for media resource locators, as taken by `Media::new_location`.
+///
+/// The format is `access[/demux]://location[#[title][:chapter]]`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Mrl {
+    access: String,
+    demux: Option<String>,
+    location: String,
+    title: Option<u32>,
+    chapter: Option<u32>,
+}
+
+impl Mrl {
+    /// Create a MRL from an access module name (like "http") and a location.
+    /// The location is used as is; it must already be escaped.
+    pub fn new(access: &str, location: &str) -> Mrl {
+        Mrl {
+            access: access.to_owned(),
+            demux: None,
+            location: location.to_owned(),
+            title: None,
+            chapter: None,
+        }
+    }
+
+    /// Create a `file://` MRL from a local path, percent-encoding it.
+    /// Relative paths are resolved against the current directory.
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Mrl, Error> {
+        let path = path.as_ref();
+        let location = if path.is_absolute() {
+            encode_path(path)?
+        }else{
+            let cwd = env::current_dir().map_err(|e| Error::Io(e.kind()))?;
+            encode_path(&cwd.join(path))?
+        };
+
+        Ok(Mrl::new("file", &location))
+    }
+
+    /// Force the demux module to use.
+    pub fn demux(mut self, demux: &str) -> Mrl {
+        self.demux = Some(demux.to_owned());
+        self
+    }
+
+    /// Start at the given title.
+    pub fn title(mut self, title: u32) -> Mrl {
+        self.title = Some(title);
+        self
+    }
+
+    /// Start at the given chapter.
+    pub fn chapter(mut self, chapter: u32) -> Mrl {
+        self.chapter = Some(chapter);
+        self
+    }
+}
+
+impl fmt::Display for Mrl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.access)?;
+        if let Some(ref demux) = self.demux {
+            write!(f, "/{}", demux)?;
+        }
+        write!(f, "://{}", self.location)?;
+        if self.title.is_some() || self.chapter.is_some() {
+            write!(f, "#")?;
+            if let Some(title) = self.title {
+                write!(f, "{}", title)?;
+            }
+            if let Some(chapter) = self.chapter {
+                write!(f, ":{}", chapter)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+// Percent-encode everything but unreserved characters and `keep`.
+fn percent_encode(bytes: &[u8], keep: &[u8], out: &mut String) {
+    for &b in bytes {
+        if b.is_ascii_alphanumeric() || b"-._~".contains(&b) || keep.contains(&b) {
+            out.push(b as char);
+        }else{
+            out.push_str(&format!("%{:02X}", b));
+        }
+    }
+}
+
+// Encode an absolute path as the location part of a file:// URI ("/dir/file").
+#[cfg(unix)]
+fn encode_path(path: &Path) -> Result<String, Error> {
+    use std::os::unix::ffi::OsStrExt;
+
+    let mut location = String::new();
+    percent_encode(path.as_os_str().as_bytes(), b"/", &mut location);
+    Ok(location)
+}
+
+// Encode an absolute path as the location part of a file:// URI:
+// "C:\dir\file" gives "/C:/dir/file" and "\\server\share\file" gives "server/share/file".
+#[cfg(not(unix))]
+fn encode_path(path: &Path) -> Result<String, Error> {
+    let path = path.to_str().ok_or(Error::InvalidArgument)?.replace('\\', "/");
+    let mut location = String::new();
+    if let Some(unc) = path.strip_prefix("//") {
+        percent_encode(unc.as_bytes(), b"/", &mut location);
+    }else{
+        let bytes = path.as_bytes();
+        if bytes.len() >= 2 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':' {
+            location.push('/');
+            location.push_str(&path[..2]);
+            percent_encode(&bytes[2..], b"/", &mut location);
+        }else{
+            percent_encode(bytes, b"/", &mut location);
+        }
+    }
+    Ok(location)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn display() {
+        assert_eq!(Mrl::new("http", "example.com/a.ts").to_string(), "http://example.com/a.ts");
+        assert_eq!(Mrl::new("dvd", "/dev/sr0").demux("ps").title(2).chapter(3).to_string(), "dvd/ps:///dev/sr0#2:3");
+        assert_eq!(Mrl::new("dvd", "/dev/sr0").title(1).to_string(), "dvd:///dev/sr0#1");
+        assert_eq!(Mrl::new("dvd", "/dev/sr0").chapter(4).to_string(), "dvd:///dev/sr0#:4");
+    }
+
+    #[test]
+    fn encode() {
+        let mut out = String::new();
+        percent_encode(b"aZ09-._~/ #?%&+:@", b"/", &mut out);
+        assert_eq!(out, "aZ09-._~/%20%23%3F%25%26%2B%3A%40");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn from_path() {
+        let mrl = Mrl::from_path("/my videos/50% off #1?.mp4").unwrap();
+        assert_eq!(mrl.to_string(), "file:///my%20videos/50%25%20off%20%231%3F.mp4");
+        let mrl = Mrl::from_path("/vidéos/日本.mkv").unwrap();
+        assert_eq!(mrl.to_string(), "file:///vid%C3%A9os/%E6%97%A5%E6%9C%AC.mkv");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn from_non_utf8_path() {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+
+        let mrl = Mrl::from_path(OsStr::from_bytes(b"/tmp/a\xff\xfe.mp4")).unwrap();
+        assert_eq!(mrl.to_string(), "file:///tmp/a%FF%FE.mp4");
+    }
+
+    #[test]
+    fn from_relative_path() {
+        let mrl = Mrl::from_path("a b.mp4").unwrap();
+        let expected = Mrl::from_path(env::current_dir().unwrap().join("a b.mp4")).unwrap();
+        assert_eq!(mrl, expected);
+        assert!(mrl.to_string().ends_with("/a%20b.mp4"));
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn from_windows_path() {
+        assert_eq!(Mrl::from_path(r"C:\my videos\a.mp4").unwrap().to_string(), "file:///C:/my%20videos/a.mp4");
+        assert_eq!(Mrl::from_path(r"\\server\share\a.mp4").unwrap().to_string(), "file://server/share/a.mp4");
+    }
+}
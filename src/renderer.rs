@@ -0,0 +1,168 @@
+// Copyright (c) 2015 T. Okubo
+// This file is part of vlc-rs.
+// Licensed under the MIT license, see the LICENSE file.
+
+use vlc_sys as sys;
+use crate::{Instance, EventManager, Error};
+use crate::tools::{to_cstr, from_cstr};
+use std::fmt;
+use std::ptr;
+use std::slice;
+
+/// The renderer can render audio.
+pub const RENDERER_CAN_AUDIO: i32 = 0x0001;
+/// The renderer can render video.
+pub const RENDERER_CAN_VIDEO: i32 = 0x0002;
+
+/// Renderer discoverer, finding renderers like Chromecasts on the network.
+/// Found and lost renderers are notified by RendererDiscovererItemAdded and
+/// RendererDiscovererItemDeleted events.
+pub struct RendererDiscoverer {
+    pub(crate) ptr: *mut sys::libvlc_renderer_discoverer_t,
+}
+
+unsafe impl Send for RendererDiscoverer {}
+
+impl RendererDiscoverer {
+    /// Create a renderer discoverer for the service with the given name.
+    /// The names are given by `Instance::renderer_discoverer_list_get`.
+    /// The discoverer has to be started with start().
+    pub fn new(instance: &Instance, name: &str) -> Result<RendererDiscoverer, Error> {
+        let cstr = to_cstr(name)?;
+        unsafe{
+            let p = sys::libvlc_renderer_discoverer_new(instance.ptr, cstr.as_ptr());
+            if p.is_null() { Err(Error::last()) }else{ Ok(RendererDiscoverer{ptr: p}) }
+        }
+    }
+
+    /// Start renderer discovery.
+    /// Attach to the events before starting, to be notified of every renderer.
+    pub fn start(&self) -> Result<(), Error> {
+        unsafe{
+            if sys::libvlc_renderer_discoverer_start(self.ptr) == 0 { Ok(()) }else{ Err(Error::last()) }
+        }
+    }
+
+    /// Stop renderer discovery.
+    pub fn stop(&self) {
+        unsafe{ sys::libvlc_renderer_discoverer_stop(self.ptr) };
+    }
+
+    /// Get the event manager of the renderer discoverer.
+    pub fn event_manager<'a>(&'a self) -> EventManager<'a> {
+        unsafe{
+            let p = sys::libvlc_renderer_discoverer_event_manager(self.ptr);
+            assert!(!p.is_null());
+            EventManager{ptr: p, _phantomdata: ::std::marker::PhantomData}
+        }
+    }
+
+    /// Returns raw pointer
+    pub fn raw(&self) -> *mut sys::libvlc_renderer_discoverer_t {
+        self.ptr
+    }
+}
+
+impl Drop for RendererDiscoverer {
+    fn drop(&mut self) {
+        unsafe{ sys::libvlc_renderer_discoverer_release(self.ptr) };
+    }
+}
+
+/// A renderer found by a RendererDiscoverer.
+/// Cloning takes a new reference; the item stays valid after the renderer is lost.
+pub struct RendererItem {
+    pub(crate) ptr: *mut sys::libvlc_renderer_item_t,
+}
+
+unsafe impl Send for RendererItem {}
+
+impl RendererItem {
+    // Take a new reference on an item owned by libvlc.
+    pub(crate) unsafe fn hold(p: *mut sys::libvlc_renderer_item_t) -> RendererItem {
+        RendererItem{ptr: sys::libvlc_renderer_item_hold(p)}
+    }
+
+    /// Human readable name of the renderer.
+    pub fn name(&self) -> String {
+        unsafe{ from_cstr(sys::libvlc_renderer_item_name(self.ptr)).unwrap_or_default() }
+    }
+
+    /// Type of the renderer, like "chromecast".
+    pub fn renderer_type(&self) -> String {
+        unsafe{ from_cstr(sys::libvlc_renderer_item_type(self.ptr)).unwrap_or_default() }
+    }
+
+    /// URI of the icon of the renderer, if any.
+    pub fn icon_uri(&self) -> Option<String> {
+        unsafe{ from_cstr(sys::libvlc_renderer_item_icon_uri(self.ptr)) }
+    }
+
+    /// Capability flags: RENDERER_CAN_AUDIO and RENDERER_CAN_VIDEO.
+    pub fn flags(&self) -> i32 {
+        unsafe{ sys::libvlc_renderer_item_flags(self.ptr) }
+    }
+
+    /// The renderer can render audio.
+    pub fn can_audio(&self) -> bool {
+        self.flags() & RENDERER_CAN_AUDIO != 0
+    }
+
+    /// The renderer can render video.
+    pub fn can_video(&self) -> bool {
+        self.flags() & RENDERER_CAN_VIDEO != 0
+    }
+
+    /// Returns raw pointer
+    pub fn raw(&self) -> *mut sys::libvlc_renderer_item_t {
+        self.ptr
+    }
+}
+
+impl Clone for RendererItem {
+    fn clone(&self) -> RendererItem {
+        unsafe{ RendererItem::hold(self.ptr) }
+    }
+}
+
+impl fmt::Debug for RendererItem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RendererItem")
+            .field("name", &self.name())
+            .field("type", &self.renderer_type())
+            .field("flags", &self.flags())
+            .finish()
+    }
+}
+
+impl Drop for RendererItem {
+    fn drop(&mut self) {
+        unsafe{ sys::libvlc_renderer_item_release(self.ptr) };
+    }
+}
+
+/// Description of a renderer discovery service.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct RendererDiscovererDescription {
+    /// Name to pass to RendererDiscoverer::new().
+    pub name: String,
+    /// Human readable name.
+    pub long_name: String,
+}
+
+pub(crate) unsafe fn renderer_discoverer_list(instance: &Instance) -> Vec<RendererDiscovererDescription> {
+    let mut services = ptr::null_mut();
+    let count = sys::libvlc_renderer_discoverer_list_get(instance.ptr, &mut services);
+    if count == 0 || services.is_null() {
+        return Vec::new();
+    }
+
+    let list = slice::from_raw_parts(services, count as usize).iter().filter_map(|&p| {
+        Some(RendererDiscovererDescription{
+            name: from_cstr((*p).psz_name)?,
+            long_name: from_cstr((*p).psz_longname).unwrap_or_default(),
+        })
+    }).collect();
+    sys::libvlc_renderer_discoverer_list_release(services, count);
+    list
+}
@@ -0,0 +1,647 @@
+// Copyright (c) 2015 T. Okubo
+// This file is part of vlc-rs.
+// Licensed under the MIT license, see the LICENSE file.
+
+use std::fmt;
+use std::str::FromStr;
+use crate::Error;
+
+// Modules which end a chain, and modules which need a next one.
+const OUTPUTS: &[&str] = &["std", "standard", "rtp", "display", "duplicate", "es", "dummy"];
+const FILTERS: &[&str] = &["transcode", "gather", "record", "setid", "setlang", "delay", "stats"];
+
+/// Value of a sout module option.
+#[derive(Clone, Debug, PartialEq)]
+pub enum SoutValue {
+    Str(String),
+    /// A nested chain, like the destinations of `duplicate` or a `venc=x264{...}`.
+    Chain(SoutChain),
+}
+
+/// One module of a sout chain, like `transcode{vcodec=h264,vb=800}`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SoutModule {
+    name: String,
+    options: Vec<(String, Option<SoutValue>)>,
+}
+
+impl SoutModule {
+    pub fn new(name: &str) -> SoutModule {
+        SoutModule {
+            name: name.to_owned(),
+            options: Vec::new(),
+        }
+    }
+
+    /// Add an option with a value. The value is escaped when rendered.
+    pub fn option<T: ToString>(mut self, key: &str, value: T) -> SoutModule {
+        self.options.push((key.to_owned(), Some(SoutValue::Str(value.to_string()))));
+        self
+    }
+
+    /// Add an option without value, like `deinterlace`.
+    pub fn flag(mut self, key: &str) -> SoutModule {
+        self.options.push((key.to_owned(), None));
+        self
+    }
+
+    /// Add an option whose value is a nested chain.
+    pub fn chain_option(mut self, key: &str, chain: SoutChain) -> SoutModule {
+        self.options.push((key.to_owned(), Some(SoutValue::Chain(chain))));
+        self
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn options(&self) -> &[(String, Option<SoutValue>)] {
+        &self.options
+    }
+
+    /// Returns the value of the first option named `key`.
+    pub fn get(&self, key: &str) -> Option<&SoutValue> {
+        self.options.iter().find(|o| o.0 == key).and_then(|o| o.1.as_ref())
+    }
+
+    fn validate(&self) -> Result<(), Error> {
+        if !is_name(&self.name) || !self.options.iter().all(|o| is_name(&o.0)) {
+            return Err(Error::InvalidArgument);
+        }
+        for value in self.options.iter().filter_map(|o| o.1.as_ref()) {
+            if let SoutValue::Chain(ref chain) = *value {
+                chain.validate_modules()?;
+            }
+        }
+
+        let has = |key| self.get(key).is_some();
+        let ok = match self.name.as_str() {
+            "std" | "standard" => has("dst"),
+            "rtp" => has("dst") || has("sdp"),
+            "duplicate" => {
+                let mut dst = false;
+                self.options.iter().all(|o| match (o.0.as_str(), &o.1) {
+                    ("dst", &Some(SoutValue::Chain(ref chain))) => {
+                        dst = true;
+                        chain.validate().is_ok()
+                    },
+                    ("dst", _) => false,
+                    ("select", _) => dst,
+                    _ => true,
+                }) && dst
+            },
+            _ => true,
+        };
+        if ok { Ok(()) }else{ Err(Error::InvalidArgument) }
+    }
+}
+
+impl fmt::Display for SoutModule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name)?;
+        if self.options.is_empty() {
+            return Ok(());
+        }
+        write!(f, "{{")?;
+        for (i, (key, value)) in self.options.iter().enumerate() {
+            if i > 0 {
+                write!(f, ",")?;
+            }
+            write!(f, "{}", key)?;
+            match value {
+                Some(SoutValue::Str(s)) => write!(f, "={}", escape(s))?,
+                Some(SoutValue::Chain(chain)) => {
+                    write!(f, "=")?;
+                    chain.fmt_modules(f)?;
+                },
+                None => (),
+            }
+        }
+        write!(f, "}}")
+    }
+}
+
+impl From<Transcode> for SoutModule {
+    fn from(transcode: Transcode) -> SoutModule {
+        transcode.0
+    }
+}
+
+impl From<Duplicate> for SoutModule {
+    fn from(duplicate: Duplicate) -> SoutModule {
+        duplicate.0
+    }
+}
+
+/// Builder for the `transcode` module.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Transcode(SoutModule);
+
+impl Transcode {
+    pub fn new() -> Transcode {
+        Transcode(SoutModule::new("transcode"))
+    }
+
+    /// Video codec, like "h264".
+    pub fn vcodec(self, codec: &str) -> Transcode {
+        Transcode(self.0.option("vcodec", codec))
+    }
+
+    /// Video bitrate in kb/s.
+    pub fn vb(self, bitrate: u32) -> Transcode {
+        Transcode(self.0.option("vb", bitrate))
+    }
+
+    pub fn scale(self, scale: f32) -> Transcode {
+        Transcode(self.0.option("scale", scale))
+    }
+
+    pub fn width(self, width: u32) -> Transcode {
+        Transcode(self.0.option("width", width))
+    }
+
+    pub fn height(self, height: u32) -> Transcode {
+        Transcode(self.0.option("height", height))
+    }
+
+    pub fn fps(self, fps: f32) -> Transcode {
+        Transcode(self.0.option("fps", fps))
+    }
+
+    pub fn deinterlace(self) -> Transcode {
+        Transcode(self.0.flag("deinterlace"))
+    }
+
+    /// Audio codec, like "mp4a".
+    pub fn acodec(self, codec: &str) -> Transcode {
+        Transcode(self.0.option("acodec", codec))
+    }
+
+    /// Audio bitrate in kb/s.
+    pub fn ab(self, bitrate: u32) -> Transcode {
+        Transcode(self.0.option("ab", bitrate))
+    }
+
+    pub fn channels(self, channels: u32) -> Transcode {
+        Transcode(self.0.option("channels", channels))
+    }
+
+    pub fn samplerate(self, rate: u32) -> Transcode {
+        Transcode(self.0.option("samplerate", rate))
+    }
+
+    /// Subtitle codec, like "dvbs".
+    pub fn scodec(self, codec: &str) -> Transcode {
+        Transcode(self.0.option("scodec", codec))
+    }
+
+    /// Render subtitles into the video.
+    pub fn soverlay(self) -> Transcode {
+        Transcode(self.0.flag("soverlay"))
+    }
+
+    /// Any other transcode option.
+    pub fn option<T: ToString>(self, key: &str, value: T) -> Transcode {
+        Transcode(self.0.option(key, value))
+    }
+}
+
+impl Default for Transcode {
+    fn default() -> Transcode {
+        Transcode::new()
+    }
+}
+
+/// Builder for the `duplicate` module, which sends the stream to several chains.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Duplicate(SoutModule);
+
+impl Duplicate {
+    pub fn new() -> Duplicate {
+        Duplicate(SoutModule::new("duplicate"))
+    }
+
+    /// Add a destination chain.
+    pub fn dst(self, chain: SoutChain) -> Duplicate {
+        Duplicate(self.0.chain_option("dst", chain))
+    }
+
+    /// Only send the elementary streams matching `select` (like "es=1" or
+    /// "noaudio") to the last added destination.
+    pub fn select(self, select: &str) -> Duplicate {
+        Duplicate(self.0.option("select", select))
+    }
+}
+
+impl Default for Duplicate {
+    fn default() -> Duplicate {
+        Duplicate::new()
+    }
+}
+
+/// A stream output chain, like `#transcode{vcodec=h264}:std{access=http,mux=ts,dst=:8080/x}`.
+///
+/// `build` renders it for `Vlm::add_broadcast`, and `media_option` for `Media::add_option`.
+/// Parsing the rendered string gives back the same chain.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SoutChain {
+    modules: Vec<SoutModule>,
+}
+
+impl SoutChain {
+    pub fn new() -> SoutChain {
+        SoutChain { modules: Vec::new() }
+    }
+
+    /// Append a module.
+    pub fn module<M: Into<SoutModule>>(mut self, module: M) -> SoutChain {
+        self.modules.push(module.into());
+        self
+    }
+
+    pub fn transcode(self, transcode: Transcode) -> SoutChain {
+        self.module(transcode)
+    }
+
+    /// Append a `std` output, like `std{access=http,mux=ts,dst=:8080/x}`.
+    pub fn std(self, access: &str, mux: &str, dst: &str) -> SoutChain {
+        self.module(SoutModule::new("std").option("access", access).option("mux", mux).option("dst", dst))
+    }
+
+    /// Serve over HTTP; `dst` is like ":8080/stream".
+    pub fn http(self, mux: &str, dst: &str) -> SoutChain {
+        self.std("http", mux, dst)
+    }
+
+    /// Send over UDP; `dst` is like "239.0.0.1:1234".
+    pub fn udp(self, mux: &str, dst: &str) -> SoutChain {
+        self.std("udp", mux, dst)
+    }
+
+    /// Write to a file.
+    pub fn file(self, mux: &str, path: &str) -> SoutChain {
+        self.std("file", mux, path)
+    }
+
+    /// Send over RTP to `dst` (a host), starting at `port`.
+    pub fn rtp(self, dst: &str, port: u16) -> SoutChain {
+        self.module(SoutModule::new("rtp").option("dst", dst).option("port", port))
+    }
+
+    /// Display the stream locally.
+    pub fn display(self) -> SoutChain {
+        self.module(SoutModule::new("display"))
+    }
+
+    pub fn duplicate(self, duplicate: Duplicate) -> SoutChain {
+        self.module(duplicate)
+    }
+
+    pub fn modules(&self) -> &[SoutModule] {
+        &self.modules
+    }
+
+    /// Check the chain structure: it is not empty, names are valid, only the
+    /// last module is an output (like `std` or `duplicate`) and it is not a filter
+    /// (like `transcode`), outputs have a destination and `duplicate` only
+    /// holds valid chains.
+    pub fn validate(&self) -> Result<(), Error> {
+        self.validate_modules()?;
+        let last = self.modules.len() - 1;
+        for (i, module) in self.modules.iter().enumerate() {
+            let name = module.name.as_str();
+            if (i < last && OUTPUTS.contains(&name)) || (i == last && FILTERS.contains(&name)) {
+                return Err(Error::InvalidArgument);
+            }
+        }
+        Ok(())
+    }
+
+    // Checks which also apply to nested chains, which need not end with an output.
+    fn validate_modules(&self) -> Result<(), Error> {
+        if self.modules.is_empty() {
+            return Err(Error::InvalidArgument);
+        }
+        self.modules.iter().try_for_each(SoutModule::validate)
+    }
+
+    /// Validate and render the chain, as taken by `Vlm::add_broadcast`.
+    pub fn build(&self) -> Result<String, Error> {
+        self.validate()?;
+        Ok(self.to_string())
+    }
+
+    /// Validate and render the chain as a `:sout=` option, as taken by `Media::add_option`.
+    pub fn media_option(&self) -> Result<String, Error> {
+        Ok(format!(":sout={}", self.build()?))
+    }
+
+    /// Parse a chain string. The leading `#` is required.
+    pub fn parse(s: &str) -> Result<SoutChain, Error> {
+        let mut parser = Parser{s, pos: 0};
+        parser.skip_ws();
+        if !parser.eat(b'#') {
+            return Err(parser.error("expected '#'"));
+        }
+        let chain = parser.chain()?;
+        parser.skip_ws();
+        if parser.pos < s.len() {
+            return Err(parser.error("trailing data"));
+        }
+        Ok(chain)
+    }
+
+    fn fmt_modules(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, module) in self.modules.iter().enumerate() {
+            if i > 0 {
+                write!(f, ":")?;
+            }
+            write!(f, "{}", module)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for SoutChain {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "#")?;
+        self.fmt_modules(f)
+    }
+}
+
+impl FromStr for SoutChain {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<SoutChain, Error> {
+        SoutChain::parse(s)
+    }
+}
+
+fn is_name(s: &str) -> bool {
+    !s.is_empty() && s.bytes().all(|b| b.is_ascii_alphanumeric() || b"-_.".contains(&b))
+}
+
+// Quote values the chain parser would otherwise split or trim, and values with
+// spaces which VLC needs quoted, escaping like config_StringEscape.
+fn escape(s: &str) -> String {
+    let special = |c: char| c.is_whitespace() || ",{}\"'\\".contains(c);
+    if !s.is_empty() && !s.contains(special) {
+        return s.to_owned();
+    }
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        if "\"'\\".contains(c) {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out.push('"');
+    out
+}
+
+struct Parser<'a> {
+    s: &'a str,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn error(&self, what: &str) -> Error {
+        Error::Parse(format!("{} at offset {} of sout chain", what, self.pos))
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.s.as_bytes().get(self.pos).cloned()
+    }
+
+    fn eat(&mut self, c: u8) -> bool {
+        if self.peek() == Some(c) {
+            self.pos += 1;
+            true
+        }else{
+            false
+        }
+    }
+
+    fn skip_ws(&mut self) {
+        let rest = &self.s[self.pos..];
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+
+    fn name(&mut self) -> Result<String, Error> {
+        self.skip_ws();
+        let start = self.pos;
+        while self.peek().is_some_and(|b| b.is_ascii_alphanumeric() || b"-_.".contains(&b)) {
+            self.pos += 1;
+        }
+        if self.pos == start {
+            return Err(self.error("expected a name"));
+        }
+        Ok(self.s[start..self.pos].to_owned())
+    }
+
+    fn chain(&mut self) -> Result<SoutChain, Error> {
+        let mut chain = SoutChain::new();
+        loop {
+            chain.modules.push(self.module()?);
+            self.skip_ws();
+            if !self.eat(b':') {
+                return Ok(chain);
+            }
+        }
+    }
+
+    fn module(&mut self) -> Result<SoutModule, Error> {
+        let mut module = SoutModule::new(&self.name()?);
+        self.skip_ws();
+        if !self.eat(b'{') {
+            return Ok(module);
+        }
+        loop {
+            self.skip_ws();
+            if self.eat(b'}') {
+                return Ok(module);
+            }
+            let key = self.name()?;
+            self.skip_ws();
+            let value = if self.eat(b'=') {
+                Some(self.value(&module.name, &key)?)
+            }else{
+                None
+            };
+            module.options.push((key, value));
+            self.skip_ws();
+            if !self.eat(b',') && self.peek() != Some(b'}') {
+                return Err(self.error("expected ',' or '}'"));
+            }
+        }
+    }
+
+    fn value(&mut self, module: &str, key: &str) -> Result<SoutValue, Error> {
+        self.skip_ws();
+        match self.peek() {
+            Some(q) if q == b'"' || q == b'\'' => return self.quoted(q).map(SoutValue::Str),
+            _ => (),
+        }
+
+        // Values holding module configurations are nested chains.
+        let start = self.pos;
+        let end = self.value_end();
+        if (module == "duplicate" && key == "dst") || self.s[start..end].contains('{') {
+            if let Ok(chain) = self.chain() {
+                self.skip_ws();
+                if self.pos == end {
+                    return Ok(SoutValue::Chain(chain));
+                }
+            }
+        }
+        self.pos = end;
+        Ok(SoutValue::Str(unescape(self.s[start..end].trim_end())))
+    }
+
+    // End of an unquoted value: the next ',' or '}' outside of braces and quotes.
+    fn value_end(&self) -> usize {
+        let bytes = self.s.as_bytes();
+        let mut depth = 0;
+        let mut quote = None;
+        let mut i = self.pos;
+        while i < bytes.len() {
+            match (bytes[i], quote) {
+                (b'\\', Some(_)) => i += 1,
+                (c, Some(q)) if c == q => quote = None,
+                (_, Some(_)) => (),
+                (c, None) if c == b'"' || c == b'\'' => quote = Some(c),
+                (b'{', None) => depth += 1,
+                (b'}', None) if depth == 0 => break,
+                (b'}', None) => depth -= 1,
+                (b',', None) if depth == 0 => break,
+                _ => (),
+            }
+            i += 1;
+        }
+        i.min(bytes.len())
+    }
+
+    fn quoted(&mut self, quote: u8) -> Result<String, Error> {
+        self.pos += 1;
+        let start = self.pos;
+        let bytes = self.s.as_bytes();
+        while self.pos < bytes.len() {
+            match bytes[self.pos] {
+                b'\\' => self.pos += 2,
+                c if c == quote => {
+                    let value = unescape(&self.s[start..self.pos]);
+                    self.pos += 1;
+                    return Ok(value);
+                },
+                _ => self.pos += 1,
+            }
+        }
+        Err(self.error("unterminated quoted value"))
+    }
+}
+
+// Like config_StringUnescape: drop the backslash before quotes and backslashes.
+fn unescape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut chars = s.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c == '\\' && chars.peek().is_some_and(|&n| "\"'\\".contains(n)) {
+            continue;
+        }
+        out.push(c);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(chain: &SoutChain) -> String {
+        let built = chain.build().unwrap();
+        assert_eq!(&SoutChain::parse(&built).unwrap(), chain);
+        built
+    }
+
+    #[test]
+    fn quote_values_with_spaces() {
+        let chain = SoutChain::new().file("ts", "/my videos/a.ts");
+        assert_eq!(round_trip(&chain), r#"#std{access=file,mux=ts,dst="/my videos/a.ts"}"#);
+    }
+
+    #[test]
+    fn escape_quotes() {
+        let chain = SoutChain::new()
+            .module(SoutModule::new("std").option("access", "file").option("mux", "ts").option("dst", r#"/tmp/"it's"\a.ts"#));
+        assert_eq!(round_trip(&chain), r#"#std{access=file,mux=ts,dst="/tmp/\"it\'s\"\\a.ts"}"#);
+    }
+
+    #[test]
+    fn nested_chains() {
+        let x264 = SoutChain::new().module(SoutModule::new("x264").option("profile", "baseline").option("preset", "fast"));
+        let chain = SoutChain::new()
+            .transcode(Transcode::new().vcodec("h264").vb(800).deinterlace().option("venc", "x264").soverlay())
+            .module(SoutModule::new("transcode").chain_option("venc", x264))
+            .duplicate(Duplicate::new()
+                .dst(SoutChain::new().display())
+                .dst(SoutChain::new().http("ts", ":8080/x"))
+                .select("noaudio")
+                .dst(SoutChain::new().transcode(Transcode::new().acodec("mp4a")).udp("ts", "239.0.0.1:1234")));
+        assert_eq!(round_trip(&chain), concat!(
+            "#transcode{vcodec=h264,vb=800,deinterlace,venc=x264,soverlay}",
+            ":transcode{venc=x264{profile=baseline,preset=fast}}",
+            ":duplicate{dst=display,dst=std{access=http,mux=ts,dst=:8080/x},select=noaudio,",
+            "dst=transcode{acodec=mp4a}:std{access=udp,mux=ts,dst=239.0.0.1:1234}}"));
+    }
+
+    #[test]
+    fn parse_written_chain() {
+        let chain: SoutChain = " #transcode{ vcodec=h264 , venc=x264{preset=fast}, deinterlace }:std{access=file,mux=ts,dst='/my videos/a.ts'}"
+            .parse().unwrap();
+        let transcode = &chain.modules()[0];
+        assert_eq!(transcode.name(), "transcode");
+        assert_eq!(transcode.get("vcodec"), Some(&SoutValue::Str("h264".to_string())));
+        assert_eq!(transcode.options()[2], ("deinterlace".to_string(), None));
+        match transcode.get("venc") {
+            Some(SoutValue::Chain(venc)) => assert_eq!(venc.to_string(), "#x264{preset=fast}"),
+            value => panic!("venc is {:?}", value),
+        }
+        assert_eq!(chain.modules()[1].get("dst"), Some(&SoutValue::Str("/my videos/a.ts".to_string())));
+        assert!(chain.validate().is_ok());
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert!(SoutChain::parse("std{access=http}").is_err());
+        assert!(SoutChain::parse("#std{dst=\"a.ts}").is_err());
+        assert!(SoutChain::parse("#std{dst=a.ts").is_err());
+        assert!(SoutChain::parse("#std{dst=a.ts} x").is_err());
+    }
+
+    #[test]
+    fn reject_trailing_filter() {
+        let chain = SoutChain::new().transcode(Transcode::new().vcodec("h264"));
+        assert!(chain.validate().is_err());
+        assert!(chain.build().is_err());
+    }
+
+    #[test]
+    fn reject_output_without_dst() {
+        let chain = SoutChain::new().module(SoutModule::new("std").option("access", "http").option("mux", "ts"));
+        assert!(chain.validate().is_err());
+    }
+
+    #[test]
+    fn reject_select_before_dst() {
+        let duplicate = Duplicate::new().select("noaudio").dst(SoutChain::new().display());
+        assert!(SoutChain::new().duplicate(duplicate).validate().is_err());
+    }
+
+    #[test]
+    fn reject_output_before_last() {
+        let chain = SoutChain::new().display().http("ts", ":8080/x");
+        assert!(chain.validate().is_err());
+        assert!(SoutChain::new().validate().is_err());
+    }
+}
@@ -0,0 +1,93 @@
+// Copyright (c) 2015 T. Okubo
+// This file is part of vlc-rs.
+// Licensed under the MIT license, see the LICENSE file.
+
+use std::collections::VecDeque;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+use futures_core::Stream;
+use crate::{Error, Event, EventType, EventManager, EventSubscription};
+
+/// What an EventStream does with an event when its buffer is full.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum OverflowPolicy {
+    /// Discard the oldest buffered event to make room for the new one.
+    DropOldest,
+    /// Discard the new event.
+    DropNewest,
+}
+
+struct Buffer {
+    events: VecDeque<Event>,
+    capacity: usize,
+    policy: OverflowPolicy,
+    dropped: u64,
+    waker: Option<Waker>,
+}
+
+impl Buffer {
+    fn push(&mut self, event: Event) {
+        if self.events.len() >= self.capacity {
+            self.dropped += 1;
+            match self.policy {
+                OverflowPolicy::DropOldest => { self.events.pop_front(); },
+                OverflowPolicy::DropNewest => return,
+            }
+        }
+        self.events.push_back(event);
+        if let Some(waker) = self.waker.take() {
+            waker.wake();
+        }
+    }
+}
+
+/// Stream of events returned by EventManager::stream.
+/// The events are detached when the stream is dropped; it never ends otherwise.
+pub struct EventStream<'a> {
+    buffer: Arc<Mutex<Buffer>>,
+    _subscriptions: Vec<EventSubscription<'a>>,
+}
+
+impl<'a> EventStream<'a> {
+    /// Number of events discarded so far because the buffer was full.
+    pub fn dropped(&self) -> u64 {
+        self.buffer.lock().unwrap().dropped
+    }
+}
+
+impl<'a> Stream for EventStream<'a> {
+    type Item = Event;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Event>> {
+        let mut buffer = self.buffer.lock().unwrap();
+        match buffer.events.pop_front() {
+            Some(event) => Poll::Ready(Some(event)),
+            None => {
+                buffer.waker = Some(cx.waker().clone());
+                Poll::Pending
+            },
+        }
+    }
+}
+
+impl<'a> EventManager<'a> {
+    /// Register for a set of event types and receive the events through a `futures::Stream`.
+    /// At most `capacity` events are buffered, further events are handled according to `policy`.
+    pub fn stream(&self, event_types: &[EventType], capacity: usize, policy: OverflowPolicy) -> Result<EventStream<'a>, Error> {
+        let buffer = Arc::new(Mutex::new(Buffer {
+            events: VecDeque::with_capacity(capacity.max(1)),
+            capacity: capacity.max(1),
+            policy,
+            dropped: 0,
+            waker: None,
+        }));
+
+        let subscriptions = event_types.iter().map(|&event_type| {
+            let buffer = buffer.clone();
+            self.attach(event_type, move |e, _| buffer.lock().unwrap().push(e))
+        }).collect::<Result<Vec<_>, Error>>()?;
+
+        Ok(EventStream{buffer, _subscriptions: subscriptions})
+    }
+}
@@ -2,15 +2,16 @@
 // This file is part of vlc-rs.
 // Licensed under the MIT license, see the LICENSE file.
 
-use std::ffi::{CString, CStr, NulError};
+use std::ffi::{CString, CStr};
 use std::path::Path;
 use std::borrow::Cow;
 use libc::c_char;
+use crate::Error;
 
 // Convert String to CString.
-// Panic if the string includes null bytes.
-pub fn to_cstr(s: &str) -> CString {
-    CString::new(s.to_owned()).expect("Error: Unexpected null byte")
+// Fail if the string includes null bytes.
+pub fn to_cstr(s: &str) -> Result<CString, Error> {
+    Ok(CString::new(s)?)
 }
 
 // Convert *const c_char to String
@@ -36,8 +37,17 @@ pub unsafe fn from_cstr_ref<'a>(p: *const c_char) -> Option<Cow<'a, str>> {
 }
 
 // Create CString from &Path
-pub fn path_to_cstr(path: &Path) -> Result<CString, NulError> {
-    let path = CString::new(path.to_string_lossy().into_owned())?;
+// The raw bytes are passed through on Unix, elsewhere the path has to be valid Unicode.
+#[cfg(unix)]
+pub fn path_to_cstr(path: &Path) -> Result<CString, Error> {
+    use std::os::unix::ffi::OsStrExt;
 
-    Ok(path)
+    Ok(CString::new(path.as_os_str().as_bytes())?)
+}
+
+#[cfg(not(unix))]
+pub fn path_to_cstr(path: &Path) -> Result<CString, Error> {
+    let path = path.to_str().ok_or(Error::InvalidArgument)?;
+
+    to_cstr(path)
 }
@@ -0,0 +1,130 @@
+// Copyright (c) 2015 T. Okubo
+// This file is part of vlc-rs.
+// Licensed under the MIT license, see the LICENSE file.
+
+use vlc_sys as sys;
+use crate::{MediaPlayer, TrackDescription, TrackType, Event, EventType, EventReceiver, Error};
+use crate::media_player::track_description_list;
+
+impl MediaPlayer {
+    /// Get the audio, video or subtitle (`TrackType::Text`) tracks of the current media.
+    /// The lists include the "Disable" entry (id -1) reported by libvlc.
+    pub fn tracks(&self, kind: TrackType) -> Vec<TrackDescription> {
+        let list = unsafe{
+            match kind {
+                TrackType::Audio => track_description_list(sys::libvlc_audio_get_track_description(self.ptr)),
+                TrackType::Video => track_description_list(sys::libvlc_video_get_track_description(self.ptr)),
+                TrackType::Text => track_description_list(sys::libvlc_video_get_spu_description(self.ptr)),
+                TrackType::Unknown => None,
+            }
+        };
+        list.unwrap_or_default()
+    }
+
+    /// Get the id of the selected track of a type, if any.
+    pub fn selected_track(&self, kind: TrackType) -> Option<i32> {
+        let id = unsafe{
+            match kind {
+                TrackType::Audio => sys::libvlc_audio_get_track(self.ptr),
+                TrackType::Video => sys::libvlc_video_get_track(self.ptr),
+                TrackType::Text => sys::libvlc_video_get_spu(self.ptr),
+                TrackType::Unknown => -1,
+            }
+        };
+        if id == -1 { None }else{ Some(id) }
+    }
+
+    /// Select a track by id, or disable the type with -1.
+    pub fn select_track(&self, kind: TrackType, id: i32) -> Result<(), Error> {
+        let result = unsafe{
+            match kind {
+                TrackType::Audio => sys::libvlc_audio_set_track(self.ptr, id),
+                TrackType::Video => sys::libvlc_video_set_track(self.ptr, id),
+                TrackType::Text => sys::libvlc_video_set_spu(self.ptr, id),
+                TrackType::Unknown => return Err(Error::InvalidArgument),
+            }
+        };
+        if result == 0 { Ok(()) }else{ Err(Error::last()) }
+    }
+
+    /// Create a `TrackWatcher` following the tracks of this player.
+    pub fn track_watcher(&self) -> Result<TrackWatcher<'_>, Error> {
+        TrackWatcher::new(self)
+    }
+}
+
+const TRACK_TYPES: [TrackType; 3] = [TrackType::Audio, TrackType::Video, TrackType::Text];
+
+fn index(kind: TrackType) -> Option<usize> {
+    TRACK_TYPES.iter().position(|&t| t == kind)
+}
+
+/// Track lists of a media player, kept up to date with its ES added, deleted and
+/// selected events.
+///
+/// The events are queued by libvlc threads; call `update` to apply them.
+pub struct TrackWatcher<'a> {
+    player: &'a MediaPlayer,
+    events: EventReceiver<'a>,
+    tracks: [Vec<TrackDescription>; 3],
+    selected: [Option<i32>; 3],
+}
+
+impl<'a> TrackWatcher<'a> {
+    fn new(player: &'a MediaPlayer) -> Result<TrackWatcher<'a>, Error> {
+        let events = player.event_manager().subscribe(&[
+            EventType::MediaPlayerESAdded,
+            EventType::MediaPlayerESDeleted,
+            EventType::MediaPlayerESSelected,
+        ])?;
+        // Subscribe first, so that no change is missed between the queries and the events.
+        let mut watcher = TrackWatcher {
+            player,
+            events,
+            tracks: Default::default(),
+            selected: [None; 3],
+        };
+        for (i, &kind) in TRACK_TYPES.iter().enumerate() {
+            watcher.tracks[i] = player.tracks(kind);
+            watcher.selected[i] = player.selected_track(kind);
+        }
+        Ok(watcher)
+    }
+
+    /// Apply the pending events. Returns true if a list or selection changed.
+    pub fn update(&mut self) -> bool {
+        let mut changed = false;
+        while let Ok(event) = self.events.try_recv() {
+            let (kind, id, selected) = match event {
+                Event::MediaPlayerESAdded(kind, id) | Event::MediaPlayerESDeleted(kind, id) => (kind, id, false),
+                Event::MediaPlayerESSelected(kind, id) => (kind, id, true),
+                _ => continue,
+            };
+            let i = match index(kind) {
+                Some(i) => i,
+                None => continue,
+            };
+            if selected {
+                let id = if id == -1 { None }else{ Some(id) };
+                changed |= self.selected[i] != id;
+                self.selected[i] = id;
+            }else{
+                // The events only carry ids, query the list again for the names.
+                let tracks = self.player.tracks(kind);
+                changed |= self.tracks[i] != tracks;
+                self.tracks[i] = tracks;
+            }
+        }
+        changed
+    }
+
+    /// Tracks of a type, as of the last `update`.
+    pub fn tracks(&self, kind: TrackType) -> &[TrackDescription] {
+        index(kind).map_or(&[], |i| &self.tracks[i])
+    }
+
+    /// Selected track of a type, as of the last `update`.
+    pub fn selected_track(&self, kind: TrackType) -> Option<i32> {
+        index(kind).and_then(|i| self.selected[i])
+    }
+}
@@ -5,9 +5,18 @@
 use vlc_sys as sys;
 use crate::MediaPlayer;
 use crate::TrackDescription;
-use crate::enums::VideoAdjustOption;
-use crate::tools::{to_cstr, from_cstr};
-use libc::c_void;
+use crate::{Event, EventType, Error};
+use crate::media_player::track_description_list;
+use crate::enums::{VideoAdjustOption, MarqueeOption, LogoOption, Position};
+use crate::tools::{to_cstr, from_cstr, path_to_cstr};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use libc::{c_void, c_char, c_uint};
+use std::borrow::Cow;
+use std::ptr;
+use std::slice;
+use std::sync::Mutex;
+use std::sync::mpsc::channel;
 
 pub trait MediaPlayerVideoEx {
     fn toggle_fullscreen(&self);
@@ -18,16 +27,37 @@ pub trait MediaPlayerVideoEx {
     fn get_size(&self, num: u32) -> Option<(u32, u32)>;
     fn get_video_track(&self) -> Option<i32>;
     fn set_video_track(&self, track: i32);
+    fn get_video_track_count(&self) -> i32;
     fn get_cursor(&self, num: u32) -> Option<(i32, i32)>;
     fn get_scale(&self) -> f32;
     fn set_scale(&self, factor: f32);
     fn get_aspect_ratio(&self) -> Option<String>;
-    fn set_aspect_ratio(&self, aspect: Option<&str>);
+    fn set_aspect_ratio(&self, aspect: Option<&str>) -> Result<(), Error>;
     fn get_video_track_description(&self) -> Option<Vec<TrackDescription>>;
     fn get_adjust_int(&self, option: VideoAdjustOption) -> i32;
     fn set_adjust_int(&self, option: VideoAdjustOption, value: i32);
     fn get_adjust_float(&self, option: VideoAdjustOption) -> f32;
     fn set_adjust_float(&self, option: VideoAdjustOption, value: f32);
+    fn get_spu(&self) -> Option<i32>;
+    fn set_spu(&self, spu: i32) -> Result<(), Error>;
+    fn get_spu_count(&self) -> i32;
+    fn get_spu_description(&self) -> Option<Vec<TrackDescription>>;
+    fn get_spu_delay(&self) -> Delay;
+    fn set_spu_delay(&self, delay: Delay) -> Result<(), Error>;
+    fn set_subtitle_file(&self, path: &Path) -> Result<(), Error>;
+    fn take_snapshot(&self, num: u32, path: &Path, width: u32, height: u32) -> Result<(), Error>;
+    /// Take a snapshot and wait until it is written to `path`, a file or a directory.
+    /// Returns the name of the written file, or `Error::Timeout`.
+    fn take_snapshot_and_wait(&self, num: u32, path: &Path, width: u32, height: u32, timeout: Duration) -> Result<String, Error>;
+    fn get_marquee_int(&self, option: MarqueeOption) -> i32;
+    fn get_marquee_string(&self, option: MarqueeOption) -> Option<String>;
+    fn set_marquee_int(&self, option: MarqueeOption, value: i32);
+    fn set_marquee_string(&self, option: MarqueeOption, value: &str) -> Result<(), Error>;
+    fn set_marquee(&self, marquee: &Marquee) -> Result<(), Error>;
+    fn get_logo_int(&self, option: LogoOption) -> i32;
+    fn set_logo_int(&self, option: LogoOption, value: i32);
+    fn set_logo_string(&self, option: LogoOption, value: &str) -> Result<(), Error>;
+    fn set_logo(&self, logo: &Logo) -> Result<(), Error>;
 }
 
 impl MediaPlayerVideoEx for MediaPlayer {
@@ -77,6 +107,9 @@ impl MediaPlayerVideoEx for MediaPlayer {
     fn set_video_track(&self, track: i32) {
         unsafe{ sys::libvlc_video_set_track(self.ptr, track); }
     }
+    fn get_video_track_count(&self) -> i32 {
+        unsafe{ sys::libvlc_video_get_track_count(self.ptr) }
+    }
     fn get_aspect_ratio(&self) -> Option<String> {
         unsafe{
             let p = sys::libvlc_video_get_aspect_ratio(self.ptr);
@@ -85,29 +118,18 @@ impl MediaPlayerVideoEx for MediaPlayer {
             s
         }
     }
-    fn set_aspect_ratio(&self, aspect: Option<&str>) {
+    fn set_aspect_ratio(&self, aspect: Option<&str>) -> Result<(), Error> {
         unsafe{
             if let Some(a) = aspect {
-                sys::libvlc_video_set_aspect_ratio(self.ptr, to_cstr(a).as_ptr());
+                sys::libvlc_video_set_aspect_ratio(self.ptr, to_cstr(a)?.as_ptr());
             }else{
                 sys::libvlc_video_set_aspect_ratio(self.ptr, ::std::ptr::null());
             }
         }
+        Ok(())
     }
     fn get_video_track_description(&self) -> Option<Vec<TrackDescription>> {
-        unsafe{
-            let p0 = sys::libvlc_video_get_track_description(self.ptr);
-            if p0.is_null() { return None; }
-            let mut td = Vec::new();
-            let mut p = p0;
-
-            while !(*p).p_next.is_null() {
-                td.push(TrackDescription{ id: (*p).i_id, name: from_cstr((*p).psz_name) });
-                p = (*p).p_next;
-            }
-            sys::libvlc_track_description_list_release(p0);
-            Some(td)
-        }
+        unsafe{ track_description_list(sys::libvlc_video_get_track_description(self.ptr)) }
     }
     fn get_adjust_int(&self, option: VideoAdjustOption) -> i32 {
         unsafe{ sys::libvlc_video_get_adjust_int(self.ptr, option as u32) }
@@ -121,4 +143,664 @@ impl MediaPlayerVideoEx for MediaPlayer {
     fn set_adjust_float(&self, option: VideoAdjustOption, value: f32) {
         unsafe{ sys::libvlc_video_set_adjust_float(self.ptr, option as u32, value); }
     }
+    fn get_spu(&self) -> Option<i32> {
+        unsafe{
+            let spu = sys::libvlc_video_get_spu(self.ptr);
+            if spu == -1 { None }else{ Some(spu) }
+        }
+    }
+    fn set_spu(&self, spu: i32) -> Result<(), Error> {
+        unsafe{
+            if sys::libvlc_video_set_spu(self.ptr, spu) == 0 { Ok(()) }else{ Err(Error::last()) }
+        }
+    }
+    fn get_spu_count(&self) -> i32 {
+        unsafe{ sys::libvlc_video_get_spu_count(self.ptr) }
+    }
+    fn get_spu_description(&self) -> Option<Vec<TrackDescription>> {
+        unsafe{ track_description_list(sys::libvlc_video_get_spu_description(self.ptr)) }
+    }
+    fn get_spu_delay(&self) -> Delay {
+        Delay::from_micros(unsafe{ sys::libvlc_video_get_spu_delay(self.ptr) })
+    }
+    fn set_spu_delay(&self, delay: Delay) -> Result<(), Error> {
+        unsafe{
+            if sys::libvlc_video_set_spu_delay(self.ptr, delay.as_micros()) == 0 { Ok(()) }else{ Err(Error::last()) }
+        }
+    }
+    fn set_subtitle_file(&self, path: &Path) -> Result<(), Error> {
+        let cstr = path_to_cstr(path)?;
+        unsafe{
+            if sys::libvlc_video_set_subtitle_file(self.ptr, cstr.as_ptr()) != 0 { Ok(()) }else{ Err(Error::last()) }
+        }
+    }
+    fn take_snapshot(&self, num: u32, path: &Path, width: u32, height: u32) -> Result<(), Error> {
+        let cstr = path_to_cstr(path)?;
+        unsafe{
+            if sys::libvlc_video_take_snapshot(self.ptr, num, cstr.as_ptr(), width, height) == 0 { Ok(()) }else{ Err(Error::last()) }
+        }
+    }
+    fn take_snapshot_and_wait(&self, num: u32, path: &Path, width: u32, height: u32, timeout: Duration) -> Result<String, Error> {
+        let (tx, rx) = channel();
+        let em = self.event_manager();
+        let _subscription = em.attach(EventType::MediaPlayerSnapshotTaken, move |e, _| {
+            if let Event::MediaPlayerSnapshotTaken(filename) = e {
+                let _ = tx.send(filename);
+            }
+        })?;
+
+        self.take_snapshot(num, path, width, height)?;
+        // Skip the snapshots taken by other calls.
+        let deadline = Instant::now() + timeout;
+        loop {
+            let timeout = deadline.checked_duration_since(Instant::now()).ok_or(Error::Timeout)?;
+            match rx.recv_timeout(timeout) {
+                Ok(Some(filename)) if is_snapshot_of(&filename, path) => return Ok(filename),
+                Ok(_) => (),
+                Err(_) => return Err(Error::Timeout),
+            }
+        }
+    }
+    fn get_marquee_int(&self, option: MarqueeOption) -> i32 {
+        unsafe{ sys::libvlc_video_get_marquee_int(self.ptr, option as u32) }
+    }
+    fn get_marquee_string(&self, option: MarqueeOption) -> Option<String> {
+        unsafe{
+            let p = sys::libvlc_video_get_marquee_string(self.ptr, option as u32);
+            let s = from_cstr(p);
+            if !p.is_null() { sys::libvlc_free(p as *mut c_void); }
+            s
+        }
+    }
+    fn set_marquee_int(&self, option: MarqueeOption, value: i32) {
+        unsafe{ sys::libvlc_video_set_marquee_int(self.ptr, option as u32, value); }
+    }
+    fn set_marquee_string(&self, option: MarqueeOption, value: &str) -> Result<(), Error> {
+        let cstr = to_cstr(value)?;
+        unsafe{ sys::libvlc_video_set_marquee_string(self.ptr, option as u32, cstr.as_ptr()); }
+        Ok(())
+    }
+    fn set_marquee(&self, marquee: &Marquee) -> Result<(), Error> {
+        if let Some(ref text) = marquee.text { self.set_marquee_string(MarqueeOption::Text, text)?; }
+        if let Some(color) = marquee.color { self.set_marquee_int(MarqueeOption::Color, color as i32); }
+        if let Some(opacity) = marquee.opacity { self.set_marquee_int(MarqueeOption::Opacity, opacity as i32); }
+        if let Some(position) = marquee.position { self.set_marquee_int(MarqueeOption::Position, alignment(position)); }
+        if let Some(refresh) = marquee.refresh { self.set_marquee_int(MarqueeOption::Refresh, refresh as i32); }
+        if let Some(size) = marquee.size { self.set_marquee_int(MarqueeOption::Size, size as i32); }
+        if let Some(timeout) = marquee.timeout { self.set_marquee_int(MarqueeOption::Timeout, timeout as i32); }
+        if let Some(x) = marquee.x { self.set_marquee_int(MarqueeOption::X, x); }
+        if let Some(y) = marquee.y { self.set_marquee_int(MarqueeOption::Y, y); }
+        self.set_marquee_int(MarqueeOption::Enable, if marquee.enabled { 1 }else{ 0 });
+        Ok(())
+    }
+    fn get_logo_int(&self, option: LogoOption) -> i32 {
+        unsafe{ sys::libvlc_video_get_logo_int(self.ptr, option as u32) }
+    }
+    fn set_logo_int(&self, option: LogoOption, value: i32) {
+        unsafe{ sys::libvlc_video_set_logo_int(self.ptr, option as u32, value); }
+    }
+    fn set_logo_string(&self, option: LogoOption, value: &str) -> Result<(), Error> {
+        let cstr = to_cstr(value)?;
+        unsafe{ sys::libvlc_video_set_logo_string(self.ptr, option as u32, cstr.as_ptr()); }
+        Ok(())
+    }
+    fn set_logo(&self, logo: &Logo) -> Result<(), Error> {
+        if !logo.images.is_empty() { self.set_logo_string(LogoOption::File, &logo.file_sequence()?)?; }
+        if let Some(delay) = logo.delay { self.set_logo_int(LogoOption::Delay, delay as i32); }
+        if let Some(repeat) = logo.repeat { self.set_logo_int(LogoOption::Repeat, repeat); }
+        if let Some(opacity) = logo.opacity { self.set_logo_int(LogoOption::Opacity, opacity as i32); }
+        if let Some(position) = logo.position { self.set_logo_int(LogoOption::Position, alignment(position)); }
+        if let Some(x) = logo.x { self.set_logo_int(LogoOption::X, x); }
+        if let Some(y) = logo.y { self.set_logo_int(LogoOption::Y, y); }
+        self.set_logo_int(LogoOption::Enable, if logo.enabled { 1 }else{ 0 });
+        Ok(())
+    }
+}
+
+// Convert a Position to the alignment flags used by the marquee and logo filters.
+fn alignment(position: Position) -> i32 {
+    const CENTER: i32 = 0;
+    const LEFT: i32 = 1;
+    const RIGHT: i32 = 2;
+    const TOP: i32 = 4;
+    const BOTTOM: i32 = 8;
+
+    match position {
+        Position::Disable | Position::Center => CENTER,
+        Position::Left => LEFT,
+        Position::Right => RIGHT,
+        Position::Top => TOP,
+        Position::TopLeft => TOP | LEFT,
+        Position::TopRight => TOP | RIGHT,
+        Position::Bottom => BOTTOM,
+        Position::BottomLeft => BOTTOM | LEFT,
+        Position::BottomRight => BOTTOM | RIGHT,
+    }
+}
+
+/// Marquee (text overlay) settings, applied with `MediaPlayerVideoEx::set_marquee`.
+/// Settings left unset keep their current value.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct Marquee {
+    enabled: bool,
+    text: Option<String>,
+    color: Option<u32>,
+    opacity: Option<u8>,
+    position: Option<Position>,
+    refresh: Option<u32>,
+    size: Option<u32>,
+    timeout: Option<u32>,
+    x: Option<i32>,
+    y: Option<i32>,
+}
+
+impl Default for Marquee {
+    fn default() -> Marquee {
+        Marquee {
+            enabled: true, text: None, color: None, opacity: None, position: None,
+            refresh: None, size: None, timeout: None, x: None, y: None,
+        }
+    }
+}
+
+impl Marquee {
+    /// Create settings which enable the marquee.
+    pub fn new() -> Marquee {
+        Marquee::default()
+    }
+
+    /// Enable or disable the marquee.
+    pub fn enabled(mut self, enabled: bool) -> Marquee {
+        self.enabled = enabled;
+        self
+    }
+
+    /// Text to display. Time format strings (%Y, %H, ...) and $ meta variables are expanded.
+    pub fn text<S: Into<String>>(mut self, text: S) -> Marquee {
+        self.text = Some(text.into());
+        self
+    }
+
+    /// Text color as 0xRRGGBB.
+    pub fn color(mut self, color: u32) -> Marquee {
+        self.color = Some(color & 0xFF_FFFF);
+        self
+    }
+
+    /// Text opacity, from 0 (transparent) to 255 (opaque).
+    pub fn opacity(mut self, opacity: u8) -> Marquee {
+        self.opacity = Some(opacity);
+        self
+    }
+
+    /// Position of the text relative to the video.
+    pub fn position(mut self, position: Position) -> Marquee {
+        self.position = Some(position);
+        self
+    }
+
+    /// Interval (in ms) between text refreshes.
+    pub fn refresh(mut self, refresh: u32) -> Marquee {
+        self.refresh = Some(refresh);
+        self
+    }
+
+    /// Font size in pixels.
+    pub fn size(mut self, size: u32) -> Marquee {
+        self.size = Some(size);
+        self
+    }
+
+    /// Time (in ms) the text stays displayed, 0 for ever.
+    pub fn timeout(mut self, timeout: u32) -> Marquee {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// X offset (in pixels) from the left of the video.
+    pub fn x(mut self, x: i32) -> Marquee {
+        self.x = Some(x);
+        self
+    }
+
+    /// Y offset (in pixels) from the top of the video.
+    pub fn y(mut self, y: i32) -> Marquee {
+        self.y = Some(y);
+        self
+    }
+}
+
+/// Logo (image overlay) settings, applied with `MediaPlayerVideoEx::set_logo`.
+/// Settings left unset keep their current value.
+///
+/// Several images form a sequence which is displayed in turn.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct Logo {
+    enabled: bool,
+    images: Vec<LogoImage>,
+    delay: Option<u32>,
+    repeat: Option<i32>,
+    opacity: Option<u8>,
+    position: Option<Position>,
+    x: Option<i32>,
+    y: Option<i32>,
+}
+
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+struct LogoImage {
+    path: PathBuf,
+    delay: Option<u32>,
+    opacity: Option<u8>,
+}
+
+impl Default for Logo {
+    fn default() -> Logo {
+        Logo {
+            enabled: true, images: Vec::new(), delay: None, repeat: None, opacity: None,
+            position: None, x: None, y: None,
+        }
+    }
+}
+
+impl Logo {
+    /// Create settings which enable the logo.
+    pub fn new() -> Logo {
+        Logo::default()
+    }
+
+    /// Enable or disable the logo.
+    pub fn enabled(mut self, enabled: bool) -> Logo {
+        self.enabled = enabled;
+        self
+    }
+
+    /// Display a single image file.
+    pub fn file<P: Into<PathBuf>>(mut self, path: P) -> Logo {
+        self.images.clear();
+        self.image(path, None, None)
+    }
+
+    /// Append an image to the sequence, with its own display time (in ms) and opacity
+    /// overriding the global `delay` and `opacity`.
+    pub fn image<P: Into<PathBuf>>(mut self, path: P, delay: Option<u32>, opacity: Option<u8>) -> Logo {
+        self.images.push(LogoImage{ path: path.into(), delay, opacity });
+        self
+    }
+
+    /// Time (in ms) each image of the sequence is displayed.
+    pub fn delay(mut self, delay: u32) -> Logo {
+        self.delay = Some(delay);
+        self
+    }
+
+    /// Number of loops of the image sequence, -1 to repeat for ever, 0 to display it once.
+    pub fn repeat(mut self, repeat: i32) -> Logo {
+        self.repeat = Some(repeat);
+        self
+    }
+
+    /// Logo opacity, from 0 (transparent) to 255 (opaque).
+    pub fn opacity(mut self, opacity: u8) -> Logo {
+        self.opacity = Some(opacity);
+        self
+    }
+
+    /// Position of the logo relative to the video.
+    pub fn position(mut self, position: Position) -> Logo {
+        self.position = Some(position);
+        self
+    }
+
+    /// X offset (in pixels) from the left of the video.
+    pub fn x(mut self, x: i32) -> Logo {
+        self.x = Some(x);
+        self
+    }
+
+    /// Y offset (in pixels) from the top of the video.
+    pub fn y(mut self, y: i32) -> Logo {
+        self.y = Some(y);
+        self
+    }
+
+    // Format the images as "file[,delay[,opacity]][;file...]".
+    // Paths containing the separators cannot be represented.
+    fn file_sequence(&self) -> Result<String, Error> {
+        let mut seq = Vec::with_capacity(self.images.len());
+        for image in &self.images {
+            let path = image.path.to_str().ok_or(Error::InvalidArgument)?;
+            if path.contains([',', ';']) {
+                return Err(Error::InvalidArgument);
+            }
+            let mut entry = path.to_owned();
+            if image.delay.is_some() || image.opacity.is_some() {
+                entry.push(',');
+                if let Some(delay) = image.delay { entry.push_str(&delay.to_string()); }
+            }
+            if let Some(opacity) = image.opacity {
+                entry.push(',');
+                entry.push_str(&opacity.to_string());
+            }
+            seq.push(entry);
+        }
+        Ok(seq.join(";"))
+    }
+}
+
+/// A signed time offset with microsecond precision, such as a subtitle delay.
+/// Positive values delay, negative values advance.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Default)]
+pub struct Delay(i64);
+
+impl Delay {
+    pub fn from_micros(us: i64) -> Delay {
+        Delay(us)
+    }
+
+    pub fn from_millis(ms: i64) -> Delay {
+        Delay(ms.saturating_mul(1000))
+    }
+
+    /// A delay of `duration`, or an advance of `duration` if `negative` is true.
+    pub fn from_duration(duration: Duration, negative: bool) -> Delay {
+        let us = duration.as_micros().min(i64::MAX as u128) as i64;
+        Delay(if negative { -us }else{ us })
+    }
+
+    pub fn as_micros(&self) -> i64 {
+        self.0
+    }
+
+    pub fn as_millis(&self) -> i64 {
+        self.0 / 1000
+    }
+
+    /// Absolute value of the offset.
+    pub fn abs_duration(&self) -> Duration {
+        Duration::from_micros(self.0.unsigned_abs())
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.0 < 0
+    }
+}
+
+/// Maximum number of pixel planes libvlc negotiates for a picture (`PICTURE_PLANE_MAX`).
+pub const VIDEO_MAX_PLANES: usize = 5;
+
+// Number of picture buffers reported to libvlc; frames are allocated on demand.
+const VIDEO_POOL_SIZE: c_uint = 3;
+
+/// Video format negotiated with libvlc before decoded frames are delivered.
+///
+/// The setup closure passed to `MediaPlayer::set_video_callbacks` receives the format
+/// of the source and may change the chroma and dimensions. It must fill `pitches`
+/// (bytes per scanline) and `lines` (scanlines) for every plane the chroma uses,
+/// e.g. through `VideoFormat::set_chroma`.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Default)]
+pub struct VideoFormat {
+    pub chroma: [u8; 4],
+    pub width: u32,
+    pub height: u32,
+    pub pitches: [u32; VIDEO_MAX_PLANES],
+    pub lines: [u32; VIDEO_MAX_PLANES],
+}
+
+impl VideoFormat {
+    /// Chroma as a string (a four character code such as "RV32" or "I420").
+    pub fn chroma_str(&self) -> Cow<'_, str> {
+        String::from_utf8_lossy(&self.chroma)
+    }
+
+    /// Set chroma and dimensions and compute pitches and lines for well-known chromas.
+    /// Returns false if the chroma is not known, in which case pitches and lines are left untouched.
+    ///
+    /// Pitches are rounded up to 32 bytes and lines to 16 scanlines as recommended by libvlc.
+    pub fn set_chroma(&mut self, chroma: &[u8; 4], width: u32, height: u32) -> bool {
+        // (plane count, bytes per pixel, horizontal and vertical subsampling of chroma planes)
+        let (planes, bpp, sub_x, sub_y) = match chroma {
+            b"RV32" | b"RGBA" | b"BGRA" | b"ARGB" => (1, 4, 1, 1),
+            b"RV24" => (1, 3, 1, 1),
+            b"RV16" | b"RV15" => (1, 2, 1, 1),
+            b"GREY" => (1, 1, 1, 1),
+            b"I420" | b"YV12" | b"J420" => (3, 1, 2, 2),
+            b"I422" | b"J422" => (3, 1, 2, 1),
+            b"I444" | b"J444" => (3, 1, 1, 1),
+            b"NV12" => (2, 1, 1, 2),
+            _ => return false,
+        };
+
+        self.chroma = *chroma;
+        self.width = width;
+        self.height = height;
+        self.pitches = [0; VIDEO_MAX_PLANES];
+        self.lines = [0; VIDEO_MAX_PLANES];
+        for i in 0..planes {
+            let (w, h) = if i == 0 { (width, height) }else{
+                (width.div_ceil(sub_x), height.div_ceil(sub_y))
+            };
+            // NV12 interleaves both chroma components in its second plane.
+            let w = if chroma == b"NV12" && i == 1 { w * 2 }else{ w };
+            self.pitches[i] = align_up(w * bpp, 32);
+            self.lines[i] = align_up(h, 16);
+        }
+        true
+    }
+
+    /// Size in bytes of each plane.
+    fn plane_sizes(&self) -> impl Iterator<Item = usize> + '_ {
+        self.pitches.iter().zip(self.lines.iter())
+            .map(|(&pitch, &lines)| pitch as usize * lines as usize)
+            .take_while(|&size| size != 0)
+    }
+}
+
+fn align_up(v: u32, align: u32) -> u32 {
+    v.div_ceil(align) * align
+}
+
+#[repr(C, align(32))]
+#[derive(Clone, Copy)]
+struct Align32([u8; 32]);
+
+// Heap buffer aligned on 32 bytes, as decoders expect.
+struct PlaneBuffer {
+    data: Vec<Align32>,
+    len: usize,
+}
+
+impl PlaneBuffer {
+    fn new(len: usize) -> PlaneBuffer {
+        PlaneBuffer{ data: vec![Align32([0; 32]); len.div_ceil(32)], len }
+    }
+
+    fn as_slice(&self) -> &[u8] {
+        unsafe{ slice::from_raw_parts(self.data.as_ptr() as *const u8, self.len) }
+    }
+
+    fn as_mut_slice(&mut self) -> &mut [u8] {
+        unsafe{ slice::from_raw_parts_mut(self.data.as_mut_ptr() as *mut u8, self.len) }
+    }
+}
+
+/// A decoded video frame handed to the display callback.
+pub struct VideoFrame {
+    format: VideoFormat,
+    planes: Vec<PlaneBuffer>,
+}
+
+impl VideoFrame {
+    fn new(format: VideoFormat) -> VideoFrame {
+        VideoFrame{ format, planes: format.plane_sizes().map(PlaneBuffer::new).collect() }
+    }
+
+    /// Format of this frame.
+    pub fn format(&self) -> &VideoFormat {
+        &self.format
+    }
+
+    /// Number of pixel planes.
+    pub fn plane_count(&self) -> usize {
+        self.planes.len()
+    }
+
+    /// Pixels of a plane, `format().pitches[index] * format().lines[index]` bytes long.
+    pub fn plane(&self, index: usize) -> Option<&[u8]> {
+        self.planes.get(index).map(|p| p.as_slice())
+    }
+
+    /// Pixels of all planes.
+    pub fn planes(&self) -> impl Iterator<Item = &[u8]> {
+        self.planes.iter().map(|p| p.as_slice())
+    }
+}
+
+// Frames not currently used by libvlc, reused for the format they were allocated with.
+// Unlocked frames stay pending until displayed, libvlc calls display after unlock.
+// Frames are boxed as libvlc holds pointers to them.
+#[allow(clippy::vec_box)]
+#[derive(Default)]
+struct FramePool {
+    format: VideoFormat,
+    free: Vec<Box<VideoFrame>>,
+    pending: Vec<Box<VideoFrame>>,
+}
+
+impl FramePool {
+    fn recycle(&mut self, frame: Box<VideoFrame>) {
+        if frame.format == self.format {
+            self.free.push(frame);
+        }
+    }
+}
+
+// Whether a snapshot written to `filename` was requested with `path`, the file itself
+// or the directory libvlc names the file in.
+fn is_snapshot_of(filename: &str, path: &Path) -> bool {
+    let filename = Path::new(filename);
+    filename == path || filename.parent() == Some(path)
+}
+
+pub(crate) type VideoSetupCb = Box<dyn FnMut(&mut VideoFormat) -> bool + Send + 'static>;
+pub(crate) type VideoLockCb = Box<dyn FnMut(&mut [&mut [u8]]) + Send + 'static>;
+pub(crate) type VideoDisplayCb = Box<dyn FnMut(&VideoFrame) + Send + 'static>;
+pub(crate) type VideoCleanupCb = Box<dyn FnMut() + Send + 'static>;
+
+// For video_set_callbacks and video_set_format_callbacks
+pub(crate) struct VideoCallbacksData {
+    setup: Mutex<VideoSetupCb>,
+    lock: Mutex<Option<VideoLockCb>>,
+    display: Mutex<VideoDisplayCb>,
+    cleanup: Mutex<Option<VideoCleanupCb>>,
+    pool: Mutex<FramePool>,
+}
+
+impl VideoCallbacksData {
+    pub(crate) fn new(
+        setup: VideoSetupCb,
+        lock: Option<VideoLockCb>,
+        display: VideoDisplayCb,
+        cleanup: Option<VideoCleanupCb>) -> VideoCallbacksData
+    {
+        VideoCallbacksData {
+            setup: Mutex::new(setup), lock: Mutex::new(lock), display: Mutex::new(display),
+            cleanup: Mutex::new(cleanup), pool: Mutex::new(FramePool::default()),
+        }
+    }
+}
+
+pub(crate) unsafe extern "C" fn video_cb_format(
+    opaque: *mut *mut c_void, chroma: *mut c_char, width: *mut c_uint, height: *mut c_uint,
+    pitches: *mut c_uint, lines: *mut c_uint) -> c_uint {
+    let data = &*(*opaque as *const VideoCallbacksData);
+
+    let mut format = VideoFormat {
+        width: *width, height: *height,
+        ..Default::default()
+    };
+    ptr::copy_nonoverlapping(chroma as *const u8, format.chroma.as_mut_ptr(), 4);
+
+    if !(data.setup.lock().unwrap())(&mut format) || format.plane_sizes().next().is_none() {
+        return 0;
+    }
+
+    ptr::copy_nonoverlapping(format.chroma.as_ptr(), chroma as *mut u8, 4);
+    *width = format.width;
+    *height = format.height;
+    for i in 0..VIDEO_MAX_PLANES {
+        *pitches.add(i) = format.pitches[i];
+        *lines.add(i) = format.lines[i];
+    }
+
+    let mut pool = data.pool.lock().unwrap();
+    pool.format = format;
+    pool.free.clear();
+    VIDEO_POOL_SIZE
+}
+
+pub(crate) unsafe extern "C" fn video_cb_cleanup(opaque: *mut c_void) {
+    let data = &*(opaque as *const VideoCallbacksData);
+    if let Some(cleanup) = data.cleanup.lock().unwrap().as_mut() {
+        cleanup();
+    }
+    let mut pool = data.pool.lock().unwrap();
+    pool.free.clear();
+    pool.pending.clear();
+}
+
+pub(crate) unsafe extern "C" fn video_cb_lock(opaque: *mut c_void, planes: *mut *mut c_void) -> *mut c_void {
+    let data = &*(opaque as *const VideoCallbacksData);
+
+    let mut frame = {
+        let mut pool = data.pool.lock().unwrap();
+        let format = pool.format;
+        pool.free.pop().unwrap_or_else(|| Box::new(VideoFrame::new(format)))
+    };
+
+    if let Some(lock) = data.lock.lock().unwrap().as_mut() {
+        let mut buffers: Vec<&mut [u8]> = frame.planes.iter_mut().map(|p| p.as_mut_slice()).collect();
+        lock(&mut buffers);
+    }
+
+    for i in 0..VIDEO_MAX_PLANES {
+        *planes.add(i) = match frame.planes.get_mut(i) {
+            Some(p) => p.as_mut_slice().as_mut_ptr() as *mut c_void,
+            None => ptr::null_mut(),
+        };
+    }
+    Box::into_raw(frame) as *mut c_void
+}
+
+pub(crate) unsafe extern "C" fn video_cb_unlock(opaque: *mut c_void, picture: *mut c_void, _planes: *const *mut c_void) {
+    let data = &*(opaque as *const VideoCallbacksData);
+    data.pool.lock().unwrap().pending.push(Box::from_raw(picture as *mut VideoFrame));
+}
+
+pub(crate) unsafe extern "C" fn video_cb_display(opaque: *mut c_void, picture: *mut c_void) {
+    let data = &*(opaque as *const VideoCallbacksData);
+    let frame = &*(picture as *const VideoFrame);
+    (data.display.lock().unwrap())(frame);
+
+    // Frames unlocked before this one were dropped without being displayed.
+    let mut pool = data.pool.lock().unwrap();
+    if let Some(i) = pool.pending.iter().position(|f| ptr::eq(&**f, frame)) {
+        let done: Vec<_> = pool.pending.drain(..=i).collect();
+        for f in done {
+            pool.recycle(f);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn snapshot_paths() {
+        assert!(is_snapshot_of("/tmp/shot.png", Path::new("/tmp/shot.png")));
+        assert!(is_snapshot_of("/tmp/vlcsnap-2020-01-01-00h00m00s000.png", Path::new("/tmp")));
+        assert!(is_snapshot_of("/tmp/vlcsnap.png", Path::new("/tmp/")));
+        assert!(!is_snapshot_of("/tmp/other.png", Path::new("/tmp/shot.png")));
+        assert!(!is_snapshot_of("/home/vlcsnap.png", Path::new("/tmp")));
+    }
 }
@@ -1,148 +1,414 @@
-use std::ffi::CString;
+use std::cell::RefCell;
+use std::ffi::{CStr, CString};
+use std::fs;
 use std::os::raw::c_char;
+use std::path::Path;
 use std::ptr;
 
 use vlc_sys as sys;
 use crate::Instance;
+use crate::Error;
+use crate::vlm_media::{VlmMedia, parse_media, parse_media_list};
+use crate::vlm_config;
 use crate::tools::{from_cstr, to_cstr};
 
 pub trait Vlm {
-    fn add_broadcast(&self, name: &str, input: &str, output: &str, options: Option<Vec<String>>, enabled: bool, loop_broadcast: bool, ) -> Result<(), ()>;
+    /// Add a broadcast; an empty `input` adds it without input.
+    fn add_broadcast(&self, name: &str, input: &str, output: &str, options: Option<Vec<String>>, enabled: bool, loop_broadcast: bool, ) -> Result<(), Error>;
 
-    fn add_vod(&self, name: &str, input: &str, mux: &str, options: Option<Vec<String>>, enabled: bool) -> Result<(), ()>;
+    /// Add a VoD media; an empty `input` adds it without input.
+    fn add_vod(&self, name: &str, input: &str, mux: &str, options: Option<Vec<String>>, enabled: bool) -> Result<(), Error>;
 
-    fn play_media(&self, name: &str) -> Result<(), ()>;
+    fn play_media(&self, name: &str) -> Result<(), Error>;
 
-    fn pause_media(&self, name: &str) -> Result<(), ()>;
+    fn pause_media(&self, name: &str) -> Result<(), Error>;
 
-    fn stop_media(&self, name: &str) -> Result<(), ()>;
+    fn stop_media(&self, name: &str) -> Result<(), Error>;
 
-    fn get_media_instance_position(&self, name: &str, instance: i32) -> Result<f32, ()>;
+    fn del_media(&self, name: &str) -> Result<(), Error>;
 
-    fn get_media_instance_length(&self, name: &str, instance: i32) -> Result<i32, ()>;
+    /// Replace the configuration of a media; an empty `input` removes all its inputs.
+    fn change_media(&self, name: &str, input: &str, output: &str, options: Option<Vec<String>>, enabled: bool, loop_broadcast: bool) -> Result<(), Error>;
 
-    fn get_media_instance_time(&self, name: &str, instance: i32) -> Result<i32, ()>;
+    fn set_enabled(&self, name: &str, enabled: bool) -> Result<(), Error>;
 
-    fn get_media_instance_rate(&self, name: &str, instance: i32) -> Result<i32, ()>;
+    fn set_output(&self, name: &str, output: &str) -> Result<(), Error>;
 
-    fn show_media(&self, name: &str) -> Result<String, ()>;
+    fn set_input(&self, name: &str, input: &str) -> Result<(), Error>;
+
+    fn add_input(&self, name: &str, input: &str) -> Result<(), Error>;
+
+    fn set_loop(&self, name: &str, loop_broadcast: bool) -> Result<(), Error>;
+
+    fn set_mux(&self, name: &str, mux: &str) -> Result<(), Error>;
+
+    /// Seek in the named broadcast, `percentage` going from 0.0 to 100.0.
+    fn seek_media(&self, name: &str, percentage: f32) -> Result<(), Error>;
+
+    /// Release the VLM of the instance, with all its media.
+    /// It is created again by the next VLM call.
+    ///
+    /// This destroys the VLM event manager, so it takes the instance mutably:
+    /// no subscription to `vlm_event_manager` may be alive.
+    fn release_vlm(&mut self);
+
+    fn get_media_instance_position(&self, name: &str, instance: i32) -> Result<f32, Error>;
+
+    fn get_media_instance_length(&self, name: &str, instance: i32) -> Result<i32, Error>;
+
+    fn get_media_instance_time(&self, name: &str, instance: i32) -> Result<i32, Error>;
+
+    fn get_media_instance_rate(&self, name: &str, instance: i32) -> Result<i32, Error>;
+
+    /// Describe the named media.
+    fn show_media(&self, name: &str) -> Result<VlmMedia, Error>;
+
+    /// Describe all configured media.
+    ///
+    /// The names of the media are taken from the libvlc answer. LibVLC 3 does not
+    /// print them, so the media added with this `Instance` are then shown by name,
+    /// and this fails with `Error::Parse` if the list holds others, e.g. added from
+    /// a VLM interface started with `add_intf`.
+    fn list_media(&self) -> Result<Vec<VlmMedia>, Error>;
+
+    /// Apply a VLM command file (`new`, `setup`, `control` and `del` commands), as saved
+    /// by the VLC telnet interface. Errors are reported with their line number.
+    ///
+    /// The whole file is parsed before anything is applied, but a line can still be
+    /// refused by libvlc, e.g. for a media that already exists. The lines before it
+    /// stay applied then.
+    fn load_config(&self, config: &str) -> Result<(), Error>;
+
+    /// Read and apply a VLM command file.
+    fn load_config_file<P: AsRef<Path>>(&self, path: P) -> Result<(), Error>;
+
+    /// Write the configured media as a VLM command file.
+    fn export_config(&self) -> Result<String, Error>;
+
+    /// Write the configured media to a VLM command file.
+    fn save_config_file<P: AsRef<Path>>(&self, path: P) -> Result<(), Error>;
 }
 
 impl Vlm for Instance {
-    fn add_broadcast(&self, name: &str, input: &str, output: &str, options: Option<Vec<String>>, enabled: bool, loop_broadcast: bool, ) -> Result<(), ()> {
-        let name = to_cstr(name);
-        let input = to_cstr(input);
-        let output = to_cstr(output);
+    fn add_broadcast(&self, name: &str, input: &str, output: &str, options: Option<Vec<String>>, enabled: bool, loop_broadcast: bool, ) -> Result<(), Error> {
+        let name = to_cstr(name)?;
+        let input = to_cstr(input)?;
+        let output = to_cstr(output)?;
         let opts_c_ptr: Vec<*const c_char>;
         let opts_c: Vec<CString>;
         let enabled = if enabled { 1 } else { 0 };
         let loop_broadcast = if loop_broadcast { 1 } else { 0 };
+        let registered = options.clone().unwrap_or_default();
         if let Some(vec) = options {
             opts_c = vec.into_iter()
-                .map(|x| CString::new(x).expect("Error: Unexpected null byte")).collect();
+                .map(CString::new).collect::<Result<_, _>>()?;
             opts_c_ptr = opts_c.iter().map(|x| x.as_ptr()).collect();
         } else {
             opts_c_ptr = Vec::new();
         }
         let result = unsafe {
             if opts_c_ptr.is_empty() {
-                sys::libvlc_vlm_add_broadcast(self.ptr, name.as_ptr(), input.as_ptr(), output.as_ptr(), 0, ptr::null(), enabled, loop_broadcast)
+                sys::libvlc_vlm_add_broadcast(self.ptr, name.as_ptr(), input_ptr(&input), output.as_ptr(), 0, ptr::null(), enabled, loop_broadcast)
             } else {
-                sys::libvlc_vlm_add_broadcast(self.ptr, name.as_ptr(), input.as_ptr(), output.as_ptr(), opts_c_ptr.len() as i32, opts_c_ptr.as_ptr(), enabled, loop_broadcast)
+                sys::libvlc_vlm_add_broadcast(self.ptr, name.as_ptr(), input_ptr(&input), output.as_ptr(), opts_c_ptr.len() as i32, opts_c_ptr.as_ptr(), enabled, loop_broadcast)
             }
         };
-        if result == 0 { Ok(()) } else { Err(()) }
+        if result != 0 {
+            return Err(Error::last());
+        }
+        self.vlm.add(&name, registered);
+        Ok(())
     }
 
-    fn add_vod(&self, name: &str, input: &str, mux: &str, options: Option<Vec<String>>, enabled: bool) -> Result<(), ()> {
-        let name = to_cstr(name);
-        let input = to_cstr(input);
-        let mux = to_cstr(mux);
+    fn add_vod(&self, name: &str, input: &str, mux: &str, options: Option<Vec<String>>, enabled: bool) -> Result<(), Error> {
+        let name = to_cstr(name)?;
+        let input = to_cstr(input)?;
+        let mux = to_cstr(mux)?;
         let opts_c_ptr: Vec<*const c_char>;
         let opts_c: Vec<CString>;
         let enabled = if enabled { 1 } else { 0 };
+        let registered = options.clone().unwrap_or_default();
         if let Some(vec) = options {
             opts_c = vec.into_iter()
-                .map(|x| CString::new(x).expect("Error: Unexpected null byte")).collect();
+                .map(CString::new).collect::<Result<_, _>>()?;
             opts_c_ptr = opts_c.iter().map(|x| x.as_ptr()).collect();
         } else {
             opts_c_ptr = Vec::new();
         }
         let result = unsafe {
             if opts_c_ptr.is_empty() {
-                sys::libvlc_vlm_add_vod(self.ptr, name.as_ptr(), input.as_ptr(), 0, ptr::null(), enabled, mux.as_ptr())
+                sys::libvlc_vlm_add_vod(self.ptr, name.as_ptr(), input_ptr(&input), 0, ptr::null(), enabled, mux.as_ptr())
             } else {
-                sys::libvlc_vlm_add_vod(self.ptr, name.as_ptr(), input.as_ptr(), opts_c_ptr.len() as i32, opts_c_ptr.as_ptr(), enabled, mux.as_ptr())
+                sys::libvlc_vlm_add_vod(self.ptr, name.as_ptr(), input_ptr(&input), opts_c_ptr.len() as i32, opts_c_ptr.as_ptr(), enabled, mux.as_ptr())
             }
         };
-        if result == 0 { Ok(()) } else { Err(()) }
+        if result != 0 {
+            return Err(Error::last());
+        }
+        self.vlm.add(&name, registered);
+        Ok(())
     }
 
-    fn play_media(&self, name: &str) -> Result<(), ()> {
-        let name = to_cstr(name);
+    fn play_media(&self, name: &str) -> Result<(), Error> {
+        let name = to_cstr(name)?;
         let result = unsafe {
             sys::libvlc_vlm_play_media(self.ptr, name.as_ptr())
         };
-        if result == 0 { Ok(()) } else { Err(()) }
+        if result == 0 { Ok(()) } else { Err(Error::last()) }
     }
 
-    fn pause_media(&self, name: &str) -> Result<(), ()> {
-        let name = to_cstr(name);
+    fn pause_media(&self, name: &str) -> Result<(), Error> {
+        let name = to_cstr(name)?;
         let result = unsafe {
             sys::libvlc_vlm_pause_media(self.ptr, name.as_ptr())
         };
-        if result == 0 { Ok(()) } else { Err(()) }
+        if result == 0 { Ok(()) } else { Err(Error::last()) }
     }
 
-    fn stop_media(&self, name: &str) -> Result<(), ()> {
-        let name = to_cstr(name);
+    fn stop_media(&self, name: &str) -> Result<(), Error> {
+        let name = to_cstr(name)?;
         let result = unsafe {
             sys::libvlc_vlm_stop_media(self.ptr, name.as_ptr())
         };
-        if result == 0 { Ok(()) } else { Err(()) }
+        if result == 0 { Ok(()) } else { Err(Error::last()) }
+    }
+
+    fn del_media(&self, name: &str) -> Result<(), Error> {
+        let name = to_cstr(name)?;
+        let result = unsafe {
+            sys::libvlc_vlm_del_media(self.ptr, name.as_ptr())
+        };
+        if result != 0 {
+            return Err(Error::last());
+        }
+        self.vlm.remove(&name);
+        Ok(())
+    }
+
+    fn change_media(&self, name: &str, input: &str, output: &str, options: Option<Vec<String>>, enabled: bool, loop_broadcast: bool) -> Result<(), Error> {
+        let name = to_cstr(name)?;
+        let input = to_cstr(input)?;
+        let output = to_cstr(output)?;
+        let opts_c_ptr: Vec<*const c_char>;
+        let opts_c: Vec<CString>;
+        let enabled = if enabled { 1 } else { 0 };
+        let loop_broadcast = if loop_broadcast { 1 } else { 0 };
+        let registered = options.clone().unwrap_or_default();
+        if let Some(vec) = options {
+            opts_c = vec.into_iter()
+                .map(CString::new).collect::<Result<_, _>>()?;
+            opts_c_ptr = opts_c.iter().map(|x| x.as_ptr()).collect();
+        } else {
+            opts_c_ptr = Vec::new();
+        }
+        let result = unsafe {
+            if opts_c_ptr.is_empty() {
+                sys::libvlc_vlm_change_media(self.ptr, name.as_ptr(), input_ptr(&input), output.as_ptr(), 0, ptr::null(), enabled, loop_broadcast)
+            } else {
+                sys::libvlc_vlm_change_media(self.ptr, name.as_ptr(), input_ptr(&input), output.as_ptr(), opts_c_ptr.len() as i32, opts_c_ptr.as_ptr(), enabled, loop_broadcast)
+            }
+        };
+        if result != 0 {
+            return Err(Error::last());
+        }
+        self.vlm.set_options(&name, registered);
+        Ok(())
+    }
+
+    fn set_enabled(&self, name: &str, enabled: bool) -> Result<(), Error> {
+        let name = to_cstr(name)?;
+        let result = unsafe {
+            sys::libvlc_vlm_set_enabled(self.ptr, name.as_ptr(), if enabled { 1 } else { 0 })
+        };
+        if result == 0 { Ok(()) } else { Err(Error::last()) }
+    }
+
+    fn set_output(&self, name: &str, output: &str) -> Result<(), Error> {
+        let name = to_cstr(name)?;
+        let output = to_cstr(output)?;
+        let result = unsafe {
+            sys::libvlc_vlm_set_output(self.ptr, name.as_ptr(), output.as_ptr())
+        };
+        if result == 0 { Ok(()) } else { Err(Error::last()) }
+    }
+
+    fn set_input(&self, name: &str, input: &str) -> Result<(), Error> {
+        let name = to_cstr(name)?;
+        let input = to_cstr(input)?;
+        let result = unsafe {
+            sys::libvlc_vlm_set_input(self.ptr, name.as_ptr(), input.as_ptr())
+        };
+        if result == 0 { Ok(()) } else { Err(Error::last()) }
+    }
+
+    fn add_input(&self, name: &str, input: &str) -> Result<(), Error> {
+        let name = to_cstr(name)?;
+        let input = to_cstr(input)?;
+        let result = unsafe {
+            sys::libvlc_vlm_add_input(self.ptr, name.as_ptr(), input.as_ptr())
+        };
+        if result == 0 { Ok(()) } else { Err(Error::last()) }
+    }
+
+    fn set_loop(&self, name: &str, loop_broadcast: bool) -> Result<(), Error> {
+        let name = to_cstr(name)?;
+        let result = unsafe {
+            sys::libvlc_vlm_set_loop(self.ptr, name.as_ptr(), if loop_broadcast { 1 } else { 0 })
+        };
+        if result == 0 { Ok(()) } else { Err(Error::last()) }
     }
 
-    fn get_media_instance_position(&self, name: &str, instance: i32) -> Result<f32, ()> {
-        let name = to_cstr(name);
+    fn set_mux(&self, name: &str, mux: &str) -> Result<(), Error> {
+        let name = to_cstr(name)?;
+        let mux = to_cstr(mux)?;
+        let result = unsafe {
+            sys::libvlc_vlm_set_mux(self.ptr, name.as_ptr(), mux.as_ptr())
+        };
+        if result == 0 { Ok(()) } else { Err(Error::last()) }
+    }
+
+    fn seek_media(&self, name: &str, percentage: f32) -> Result<(), Error> {
+        let name = to_cstr(name)?;
+        let result = unsafe {
+            sys::libvlc_vlm_seek_media(self.ptr, name.as_ptr(), percentage)
+        };
+        if result == 0 { Ok(()) } else { Err(Error::last()) }
+    }
+
+    fn release_vlm(&mut self) {
+        unsafe { sys::libvlc_vlm_release(self.ptr) };
+        self.vlm.clear();
+    }
+
+    fn get_media_instance_position(&self, name: &str, instance: i32) -> Result<f32, Error> {
+        let name = to_cstr(name)?;
         let result = unsafe {
             sys::libvlc_vlm_get_media_instance_position(self.ptr, name.as_ptr(), instance)
         };
-        if result != -1f32 { Ok(result) } else { Err(()) }
+        if result != -1f32 { Ok(result) } else { Err(Error::last()) }
     }
 
-    fn get_media_instance_length(&self, name: &str, instance: i32) -> Result<i32, ()> {
-        let name = to_cstr(name);
+    fn get_media_instance_length(&self, name: &str, instance: i32) -> Result<i32, Error> {
+        let name = to_cstr(name)?;
         let result = unsafe {
             sys::libvlc_vlm_get_media_instance_length(self.ptr, name.as_ptr(), instance)
         };
-        if result != -1 { Ok(result) } else { Err(()) }
+        if result != -1 { Ok(result) } else { Err(Error::last()) }
     }
 
-    fn get_media_instance_time(&self, name: &str, instance: i32) -> Result<i32, ()> {
-        let name = to_cstr(name);
+    fn get_media_instance_time(&self, name: &str, instance: i32) -> Result<i32, Error> {
+        let name = to_cstr(name)?;
         let result = unsafe {
             sys::libvlc_vlm_get_media_instance_time(self.ptr, name.as_ptr(), instance)
         };
-        if result != -1 { Ok(result) } else { Err(()) }
+        if result != -1 { Ok(result) } else { Err(Error::last()) }
     }
 
-    fn get_media_instance_rate(&self, name: &str, instance: i32) -> Result<i32, ()> {
-        let name = to_cstr(name);
+    fn get_media_instance_rate(&self, name: &str, instance: i32) -> Result<i32, Error> {
+        let name = to_cstr(name)?;
         let result = unsafe {
             sys::libvlc_vlm_get_media_instance_rate(self.ptr, name.as_ptr(), instance)
         };
-        if result != -1 { Ok(result) } else { Err(()) }
+        if result != -1 { Ok(result) } else { Err(Error::last()) }
     }
 
-    fn show_media(&self, name: &str) -> Result<String, ()> {
-        let name = to_cstr(name);
-        let result = unsafe {
-            from_cstr(sys::libvlc_vlm_show_media(self.ptr, name.as_ptr()))
-        };
-        if let Some(data) = result {
-            Ok(data.to_string())
+    fn show_media(&self, name: &str) -> Result<VlmMedia, Error> {
+        // The answer does not hold the name of the media.
+        let mut media = parse_media(name, &show(self, name)?)?;
+        self.vlm.fill_options(&mut media);
+        Ok(media)
+    }
+
+    fn list_media(&self) -> Result<Vec<VlmMedia>, Error> {
+        let list = parse_media_list(&show(self, "")?)?;
+        if list.iter().all(Option::is_some) {
+            return Ok(list.into_iter().flatten().map(|mut media| {
+                self.vlm.fill_options(&mut media);
+                media
+            }).collect());
+        }
+        // Media deleted from elsewhere cannot be shown any more, and media added
+        // from elsewhere leave the list longer than what is found by name.
+        let found: Vec<VlmMedia> = self.vlm.names().iter()
+            .filter_map(|name| self.show_media(name).ok())
+            .collect();
+        if found.len() == list.len() {
+            Ok(found)
         } else {
-            Err(())
+            Err(Error::Parse("VLM media without name".to_string()))
         }
     }
+
+    fn load_config(&self, config: &str) -> Result<(), Error> {
+        vlm_config::load(self, config)
+    }
+
+    fn load_config_file<P: AsRef<Path>>(&self, path: P) -> Result<(), Error> {
+        let config = fs::read_to_string(path).map_err(|e| Error::Io(e.kind()))?;
+        self.load_config(&config)
+    }
+
+    fn export_config(&self) -> Result<String, Error> {
+        Ok(vlm_config::export(&self.list_media()?))
+    }
+
+    fn save_config_file<P: AsRef<Path>>(&self, path: P) -> Result<(), Error> {
+        fs::write(path, self.export_config()?).map_err(|e| Error::Io(e.kind()))
+    }
+}
+
+// An empty input is passed as NULL, for a media without input.
+fn input_ptr(input: &CString) -> *const c_char {
+    if input.as_bytes().is_empty() { ptr::null() } else { input.as_ptr() }
+}
+
+/// The VLM media added through an `Instance`, in creation order, with their
+/// options: libvlc 3 prints neither the names of listed media nor options.
+#[derive(Default)]
+pub(crate) struct VlmRegistry {
+    media: RefCell<Vec<(String, Vec<String>)>>,
+}
+
+impl VlmRegistry {
+    fn add(&self, name: &CStr, options: Vec<String>) {
+        self.media.borrow_mut().push((name.to_string_lossy().into_owned(), options));
+    }
+
+    fn remove(&self, name: &CStr) {
+        self.media.borrow_mut().retain(|m| m.0.as_bytes() != name.to_bytes());
+    }
+
+    fn set_options(&self, name: &CStr, options: Vec<String>) {
+        if let Some(media) = self.media.borrow_mut().iter_mut().find(|m| m.0.as_bytes() == name.to_bytes()) {
+            media.1 = options;
+        }
+    }
+
+    fn clear(&self) {
+        self.media.borrow_mut().clear();
+    }
+
+    fn names(&self) -> Vec<String> {
+        self.media.borrow().iter().map(|m| m.0.clone()).collect()
+    }
+
+    // LibVLC 3 prints options as null, give back the ones passed through the instance.
+    fn fill_options(&self, media: &mut VlmMedia) {
+        if !media.options.is_empty() {
+            return;
+        }
+        if let Some(registered) = self.media.borrow().iter().find(|m| m.0 == media.name) {
+            media.options = registered.1.clone();
+        }
+    }
+}
+
+fn show(instance: &Instance, name: &str) -> Result<String, Error> {
+    let name = to_cstr(name)?;
+    unsafe {
+        let p = sys::libvlc_vlm_show_media(instance.ptr, name.as_ptr());
+        let result = from_cstr(p);
+        sys::libvlc_free(p as *mut _);
+        result.ok_or_else(Error::last)
+    }
 }
@@ -0,0 +1,479 @@
+// Copyright (c) 2015 T. Okubo
+// This file is part of vlc-rs.
+// Licensed under the MIT license, see the LICENSE file.
+
+// VLM command files, as written by the `save` command of the VLC telnet interface:
+//
+//     new ch1 broadcast enabled loop
+//     setup ch1 input "file:///videos/a.mp4"
+//     setup ch1 output #std{access=http,mux=ts,dst=:8080/ch1}
+//     control ch1 play
+
+use std::fmt::Write;
+use crate::{Error, Instance, Vlm, VlmMedia, VlmMediaKind};
+
+enum Property {
+    Input(String),
+    Output(String),
+    Option(String),
+    Enabled(bool),
+    Loop(bool),
+    Mux(String),
+}
+
+enum Action {
+    Play,
+    Pause,
+    Stop,
+    Seek(f32),
+}
+
+enum Command {
+    New(String, VlmMediaKind, Vec<(usize, Property)>),
+    Setup(String, Vec<(usize, Property)>),
+    Control(String, Action),
+    Del(String),
+}
+
+fn at(line: usize, error: Error) -> Error {
+    Error::AtLine(line, Box::new(error))
+}
+
+fn parse_error(line: usize, msg: String) -> Error {
+    at(line, Error::Parse(msg))
+}
+
+// Split a line into words like VLC does: quotes group words and backslashes
+// escape, but everything from an unquoted '#' on (a sout chain) is kept as is.
+fn tokenize(line: &str) -> Result<Vec<String>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = line.chars();
+    let mut token: Option<String> = None;
+    let mut quote = None;
+    let mut raw = false;
+
+    while let Some(c) = chars.next() {
+        if quote.is_none() && c.is_whitespace() {
+            tokens.extend(token.take());
+            raw = false;
+            continue;
+        }
+        let out = token.get_or_insert_with(String::new);
+        raw |= c == '#' && quote.is_none();
+        match quote {
+            None if c == '"' || c == '\'' => {
+                quote = Some(c);
+                if raw {
+                    out.push(c);
+                }
+            },
+            Some(q) if c == q => {
+                quote = None;
+                if raw {
+                    out.push(c);
+                }
+            },
+            Some(q) if c == '\\' && q != '"' => out.push(c),
+            _ if c == '\\' => {
+                let next = match chars.next() {
+                    Some(next) => next,
+                    None if quote.is_none() => break,
+                    None => return Err("unterminated escape".to_string()),
+                };
+                let escaped = if quote.is_some() {
+                    next == '"' || next == '\\'
+                }else{
+                    next == '"' || next == '\'' || next == '\\' || next.is_whitespace()
+                };
+                if raw || !escaped {
+                    out.push('\\');
+                }
+                out.push(next);
+            },
+            _ => out.push(c),
+        }
+    }
+    if quote.is_some() {
+        return Err("unterminated quote".to_string());
+    }
+    tokens.extend(token);
+    Ok(tokens)
+}
+
+fn parse_properties(line: usize, words: &[String]) -> Result<Vec<(usize, Property)>, Error> {
+    let mut props = Vec::new();
+    let mut words = words.iter();
+    while let Some(word) = words.next() {
+        let mut value = || words.next().cloned()
+            .ok_or_else(|| parse_error(line, format!("missing value for \"{}\"", word)));
+        let prop = match word.as_str() {
+            "input" => Property::Input(value()?),
+            "output" => Property::Output(value()?),
+            "option" => Property::Option(value()?),
+            "mux" => Property::Mux(value()?),
+            "enabled" => Property::Enabled(true),
+            "disabled" => Property::Enabled(false),
+            "loop" => Property::Loop(true),
+            "unloop" => Property::Loop(false),
+            _ => return Err(parse_error(line, format!("unsupported property \"{}\"", word))),
+        };
+        props.push((line, prop));
+    }
+    Ok(props)
+}
+
+fn parse_line(line: usize, words: &[String]) -> Result<Command, Error> {
+    let name = || words.get(1).cloned()
+        .ok_or_else(|| parse_error(line, format!("missing media name after \"{}\"", words[0])));
+    match words[0].as_str() {
+        "new" => {
+            let kind = match words.get(2).map(|s| s.as_str()) {
+                Some("broadcast") => VlmMediaKind::Broadcast,
+                Some("vod") => VlmMediaKind::Vod,
+                Some(kind) => return Err(parse_error(line, format!("unsupported media type \"{}\"", kind))),
+                None => return Err(parse_error(line, "missing media type".to_string())),
+            };
+            Ok(Command::New(name()?, kind, parse_properties(line, &words[3..])?))
+        },
+        "setup" => {
+            let name = name()?;
+            Ok(Command::Setup(name, parse_properties(line, &words[2..])?))
+        },
+        "control" => {
+            let name = name()?;
+            let action = match (words.get(2).map(|s| s.as_str()), words.len()) {
+                (Some("play"), 3) => Action::Play,
+                (Some("pause"), 3) => Action::Pause,
+                (Some("stop"), 3) => Action::Stop,
+                // Only seeking to a percentage is available through libvlc.
+                (Some("seek"), 4) => match words[3].parse::<f32>() {
+                    Ok(percentage) if !words[3].starts_with('+') && (0.0..=100.0).contains(&percentage) => {
+                        Action::Seek(percentage)
+                    },
+                    _ => return Err(parse_error(line, format!("unsupported seek \"{}\"", words[3]))),
+                },
+                _ => return Err(parse_error(line, "unsupported control command".to_string())),
+            };
+            Ok(Command::Control(name, action))
+        },
+        "del" => {
+            let name = name()?;
+            if words.len() > 2 || name == "schedule" {
+                return Err(parse_error(line, "unsupported del command".to_string()));
+            }
+            Ok(Command::Del(name))
+        },
+        command => Err(parse_error(line, format!("unsupported command \"{}\"", command))),
+    }
+}
+
+fn parse(config: &str) -> Result<Vec<(usize, Command)>, Error> {
+    let mut commands = Vec::new();
+    for (i, line) in config.lines().enumerate() {
+        let line_number = i + 1;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let words = tokenize(line).map_err(|msg| parse_error(line_number, msg))?;
+        commands.push((line_number, parse_line(line_number, &words)?));
+    }
+    Ok(commands)
+}
+
+fn create(vlm: &Instance, line: usize, name: &str, kind: VlmMediaKind, props: &[(usize, Property)]) -> Result<(), Error> {
+    let mut inputs = Vec::new();
+    let mut output = String::new();
+    let mut options = Vec::new();
+    let mut enabled = false;
+    let mut loop_broadcast = false;
+    let mut mux = String::new();
+    for &(prop_line, ref prop) in props {
+        match *prop {
+            Property::Input(ref input) => inputs.push(input.as_str()),
+            Property::Output(ref value) => output = value.clone(),
+            Property::Option(ref option) => options.push(option.clone()),
+            Property::Enabled(value) => enabled = value,
+            Property::Loop(value) if kind == VlmMediaKind::Broadcast => loop_broadcast = value,
+            Property::Mux(ref value) if kind == VlmMediaKind::Vod => mux = value.clone(),
+            Property::Loop(_) => return Err(parse_error(prop_line, "loop is only valid for broadcasts".to_string())),
+            Property::Mux(_) => return Err(parse_error(prop_line, "mux is only valid for VoD".to_string())),
+        }
+    }
+    let input = inputs.first().cloned().unwrap_or("");
+    let options = if options.is_empty() { None }else{ Some(options) };
+
+    match kind {
+        VlmMediaKind::Broadcast => vlm.add_broadcast(name, input, &output, options, enabled, loop_broadcast),
+        VlmMediaKind::Vod => vlm.add_vod(name, input, &mux, options, enabled).and_then(|_| {
+            if output.is_empty() { Ok(()) }else{ vlm.set_output(name, &output) }
+        }),
+    }.map_err(|e| at(line, e))?;
+    for &input in inputs.iter().skip(1) {
+        vlm.add_input(name, input).map_err(|e| at(line, e))?;
+    }
+    Ok(())
+}
+
+fn setup(vlm: &Instance, name: &str, props: &[(usize, Property)]) -> Result<(), Error> {
+    for &(line, ref prop) in props {
+        match *prop {
+            Property::Input(ref input) => vlm.add_input(name, input),
+            Property::Output(ref output) => vlm.set_output(name, output),
+            Property::Enabled(enabled) => vlm.set_enabled(name, enabled),
+            Property::Loop(loop_broadcast) => vlm.set_loop(name, loop_broadcast),
+            Property::Mux(ref mux) => vlm.set_mux(name, mux),
+            Property::Option(ref option) => add_option(vlm, name, option),
+        }.map_err(|e| at(line, e))?;
+    }
+    Ok(())
+}
+
+// libvlc has no call adding an option, the whole media has to be changed.
+fn add_option(vlm: &Instance, name: &str, option: &str) -> Result<(), Error> {
+    let media = vlm.show_media(name)?;
+    let mut options = media.options;
+    options.push(option.to_owned());
+    let output = media.output.as_deref().unwrap_or("");
+    let input = media.inputs.first().map_or("", |s| s.as_str());
+    vlm.change_media(name, input, output, Some(options), media.enabled, media.loop_broadcast)?;
+    for input in media.inputs.iter().skip(1) {
+        vlm.add_input(name, input)?;
+    }
+    Ok(())
+}
+
+/// Apply a VLM command file, line after line.
+///
+/// The file is parsed as a whole first, but a line may still fail when applied:
+/// the lines before it are not undone.
+///
+/// Setting an option on a media changes it as a whole, which drops the options
+/// that were not given through this `Instance`, as libvlc does not report them.
+pub(crate) fn load(vlm: &Instance, config: &str) -> Result<(), Error> {
+    let commands = parse(config)?;
+    for &(line, ref command) in &commands {
+        match *command {
+            Command::New(ref name, kind, ref props) => create(vlm, line, name, kind, props)?,
+            Command::Setup(ref name, ref props) => setup(vlm, name, props)?,
+            Command::Control(ref name, ref action) => match *action {
+                Action::Play => vlm.play_media(name),
+                Action::Pause => vlm.pause_media(name),
+                Action::Stop => vlm.stop_media(name),
+                Action::Seek(percentage) => vlm.seek_media(name, percentage),
+            }.map_err(|e| at(line, e))?,
+            Command::Del(ref name) if name == "all" || name == "media" => {
+                for media in vlm.list_media().map_err(|e| at(line, e))? {
+                    vlm.del_media(&media.name).map_err(|e| at(line, e))?;
+                }
+            },
+            Command::Del(ref name) => vlm.del_media(name).map_err(|e| at(line, e))?,
+        }
+    }
+    Ok(())
+}
+
+// Quote a word so that `tokenize` gives it back. Sout chains are written as they
+// are when they read back as one word, e.g. with their spaces in quotes.
+fn quote(word: &str) -> String {
+    let special = |c: char| c.is_whitespace() || c == '"' || c == '\'' || c == '\\';
+    let bare = if word.starts_with('#') {
+        tokenize(word) == Ok(vec![word.to_owned()])
+    }else{
+        !word.is_empty() && !word.contains(special)
+    };
+    if bare {
+        return word.to_owned();
+    }
+    let mut out = String::with_capacity(word.len() + 2);
+    out.push('"');
+    for c in word.chars() {
+        if c == '"' || c == '\\' {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out.push('"');
+    out
+}
+
+/// Write media descriptions as a VLM command file.
+pub(crate) fn export(media: &[VlmMedia]) -> String {
+    let mut out = String::new();
+    for m in media {
+        let name = quote(&m.name);
+        let kind = match m.kind {
+            VlmMediaKind::Broadcast => "broadcast",
+            VlmMediaKind::Vod => "vod",
+        };
+        let _ = write!(out, "new {} {} {}", name, kind, if m.enabled { "enabled" }else{ "disabled" });
+        if m.loop_broadcast {
+            out.push_str(" loop");
+        }
+        out.push('\n');
+        for input in &m.inputs {
+            let _ = writeln!(out, "setup {} input {}", name, quote(input));
+        }
+        if let Some(ref output) = m.output {
+            let _ = writeln!(out, "setup {} output {}", name, quote(output));
+        }
+        for option in &m.options {
+            let _ = writeln!(out, "setup {} option {}", name, quote(option));
+        }
+        if let Some(ref mux) = m.mux {
+            let _ = writeln!(out, "setup {} mux {}", name, quote(mux));
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn words(line: &str) -> Vec<String> {
+        tokenize(line).unwrap()
+    }
+
+    #[test]
+    fn tokenize_quotes() {
+        assert_eq!(words("new  ch1\tbroadcast "), ["new", "ch1", "broadcast"]);
+        assert_eq!(words(r#"setup "ch 1" input 'file:///a b.mp4'"#), ["setup", "ch 1", "input", "file:///a b.mp4"]);
+        assert_eq!(words(r#"setup ch1 input a"b c"d"#), ["setup", "ch1", "input", "ab cd"]);
+        assert_eq!(words(r#"setup ch1 input """#), ["setup", "ch1", "input", ""]);
+    }
+
+    #[test]
+    fn tokenize_escapes() {
+        assert_eq!(words(r#"a\ b\"c\\d\'e"#), [r#"a b"c\d'e"#]);
+        assert_eq!(words(r#""a\"b\\c\n""#), [r#"a"b\c\n"#]);
+        assert_eq!(words(r#"'a\b' \x"#), [r"a\b", r"\x"]);
+    }
+
+    #[test]
+    fn tokenize_raw_chain() {
+        assert_eq!(
+            words(r#"setup ch1 output #std{access=file,dst="/my videos/a.ts"} option x"#),
+            ["setup", "ch1", "output", r#"#std{access=file,dst="/my videos/a.ts"}"#, "option", "x"]);
+        // Escapes still group words, but are kept.
+        assert_eq!(words(r#"#a\"b\ c d"#), [r#"#a\"b\ c"#, "d"]);
+        // A quoted '#' does not start a chain.
+        assert_eq!(words(r##""#a \"b\"" 'c#d' e"##), ["#a \"b\"", "c#d", "e"]);
+    }
+
+    #[test]
+    fn tokenize_errors() {
+        assert!(tokenize(r#"setup ch1 input "a"#).is_err());
+        assert!(tokenize(r#"setup ch1 input "a\"#).is_err());
+    }
+
+    fn error_line(config: &str) -> usize {
+        match parse(config) {
+            Err(Error::AtLine(line, error)) => {
+                assert!(matches!(*error, Error::Parse(_)), "{:?}", error);
+                line
+            },
+            result => panic!("{:?}", result.map(|c| c.len())),
+        }
+    }
+
+    #[test]
+    fn errors_have_line_numbers() {
+        assert_eq!(error_line("new ch1 broadcast\n\n# comment\nsetup ch1 bogus x"), 4);
+        assert_eq!(error_line("new ch1 broadcast\nsetup ch1 input \"a"), 2);
+        assert_eq!(error_line("new ch1 playlist"), 1);
+        assert_eq!(error_line("new ch1 broadcast\nsetup ch1 output"), 2);
+        assert_eq!(error_line("\ncontrol ch1 seek 150"), 2);
+        assert_eq!(error_line("del schedule"), 1);
+        assert_eq!(error_line("show ch1"), 1);
+    }
+
+    #[test]
+    fn parse_commands() {
+        let commands = parse("new ch1 vod enabled\ncontrol ch1 seek 12.5\ndel all").unwrap();
+        assert_eq!(commands.len(), 3);
+        assert!(matches!(commands[0], (1, Command::New(ref name, VlmMediaKind::Vod, ref props)) if name == "ch1" && props.len() == 1));
+        assert!(matches!(commands[1], (2, Command::Control(_, Action::Seek(p))) if p == 12.5));
+        assert!(matches!(commands[2], (3, Command::Del(ref name)) if name == "all"));
+    }
+
+    // What libvlc would hold after the `new` and `setup` commands of a file.
+    fn apply(config: &str) -> Vec<VlmMedia> {
+        let mut list: Vec<VlmMedia> = Vec::new();
+        for (_, command) in parse(config).unwrap() {
+            let (media, props) = match command {
+                Command::New(name, kind, props) => {
+                    list.push(VlmMedia {
+                        name,
+                        kind,
+                        enabled: false,
+                        loop_broadcast: false,
+                        mux: None,
+                        inputs: Vec::new(),
+                        output: None,
+                        options: Vec::new(),
+                        instances: Vec::new(),
+                    });
+                    (list.last_mut().unwrap(), props)
+                },
+                Command::Setup(name, props) => (list.iter_mut().find(|m| m.name == name).unwrap(), props),
+                _ => panic!("unexpected command"),
+            };
+            for (_, prop) in props {
+                match prop {
+                    Property::Input(input) => media.inputs.push(input),
+                    Property::Output(output) => media.output = Some(output),
+                    Property::Option(option) => media.options.push(option),
+                    Property::Enabled(enabled) => media.enabled = enabled,
+                    Property::Loop(loop_broadcast) => media.loop_broadcast = loop_broadcast,
+                    Property::Mux(mux) => media.mux = Some(mux),
+                }
+            }
+        }
+        list
+    }
+
+    #[test]
+    fn export_load_round_trip() {
+        let media = vec![
+            VlmMedia {
+                name: "ch 1".to_string(),
+                kind: VlmMediaKind::Broadcast,
+                enabled: true,
+                loop_broadcast: true,
+                mux: None,
+                inputs: vec![r#"file:///my videos/a "b".mp4"#.to_string(), r"C:\videos\c.mp4".to_string()],
+                output: Some(r#"#std{access=file,mux=ts,dst="/my videos/a.ts"}"#.to_string()),
+                options: vec![":sout-keep".to_string(), ":input-slave=file:///a b.mp3".to_string()],
+                instances: Vec::new(),
+            },
+            VlmMedia {
+                name: "rec".to_string(),
+                kind: VlmMediaKind::Broadcast,
+                enabled: true,
+                loop_broadcast: false,
+                mux: None,
+                inputs: vec!["file:///my videos/#1.mp4".to_string(), "#a\\".to_string()],
+                output: Some("#file{dst=/my videos/a.ts}".to_string()),
+                options: Vec::new(),
+                instances: Vec::new(),
+            },
+            VlmMedia {
+                name: "movie".to_string(),
+                kind: VlmMediaKind::Vod,
+                enabled: false,
+                loop_broadcast: false,
+                mux: Some("ts".to_string()),
+                inputs: Vec::new(),
+                output: None,
+                options: Vec::new(),
+                instances: Vec::new(),
+            },
+        ];
+        let config = export(&media);
+        assert!(config.starts_with("new \"ch 1\" broadcast enabled loop\n"), "{}", config);
+        assert!(config.contains(r#"setup "ch 1" output #std{access=file,mux=ts,dst="/my videos/a.ts"}"#), "{}", config);
+        assert!(config.contains(r##"setup rec output "#file{dst=/my videos/a.ts}""##), "{}", config);
+        assert_eq!(apply(&config), media);
+    }
+}
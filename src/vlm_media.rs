@@ -0,0 +1,460 @@
+// Copyright (c) 2015 T. Okubo
+// This file is part of vlc-rs.
+// Licensed under the MIT license, see the LICENSE file.
+
+use crate::Error;
+
+/// Kind of a VLM media.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum VlmMediaKind {
+    Broadcast,
+    Vod,
+}
+
+/// Playback state of a VLM media instance.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum VlmInstanceState {
+    Playing,
+    Paused,
+    Stopped,
+}
+
+/// A VLM media, as described by `Vlm::show_media`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct VlmMedia {
+    pub name: String,
+    pub kind: VlmMediaKind,
+    pub enabled: bool,
+    /// Always `false` for VoD media.
+    pub loop_broadcast: bool,
+    /// Only set for VoD media.
+    pub mux: Option<String>,
+    pub inputs: Vec<String>,
+    pub output: Option<String>,
+    /// LibVLC 3 prints options as `null`, without their text, so these are the
+    /// options given through the `Instance`.
+    pub options: Vec<String>,
+    pub instances: Vec<VlmInstance>,
+}
+
+/// A running instance of a VLM media.
+///
+/// The playback fields are only reported while the instance has an input,
+/// and are zero otherwise.
+#[derive(Clone, Debug, PartialEq)]
+pub struct VlmInstance {
+    pub name: String,
+    pub state: VlmInstanceState,
+    pub position: f32,
+    /// Time in microseconds.
+    pub time: i64,
+    /// Length in microseconds.
+    pub length: i64,
+    pub rate: f32,
+    pub title: i32,
+    pub chapter: i32,
+    pub seekable: bool,
+    pub playlist_index: i32,
+}
+
+// Answer tree of libvlc_vlm_show_media. The answer looks like JSON, but
+// values are not escaped and object keys may repeat ("instance"). Elements of
+// lists are printed without their names, and values without text as null.
+#[derive(Debug)]
+enum Node {
+    Null,
+    Str(String),
+    List(Vec<Node>),
+    Object(Vec<(String, Node)>),
+}
+
+impl Node {
+    fn get(&self, key: &str) -> Option<&Node> {
+        match *self {
+            Node::Object(ref fields) => fields.iter().find(|f| f.0 == key).map(|f| &f.1),
+            _ => None,
+        }
+    }
+
+    fn str(&self, key: &str) -> Option<&str> {
+        match self.get(key) {
+            Some(Node::Str(s)) => Some(s),
+            _ => None,
+        }
+    }
+
+    fn parse_or_default<T: std::str::FromStr + Default>(&self, key: &str) -> T {
+        self.str(key).and_then(|s| s.trim().parse().ok()).unwrap_or_default()
+    }
+}
+
+struct Parser<'a> {
+    s: &'a str,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn error(&self, what: &str) -> Error {
+        Error::Parse(format!("{} at offset {} of VLM answer", what, self.pos))
+    }
+
+    fn skip_ws(&mut self) {
+        let rest = &self.s[self.pos..];
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+
+    fn peek(&mut self) -> Option<u8> {
+        self.skip_ws();
+        self.s.as_bytes().get(self.pos).cloned()
+    }
+
+    fn expect(&mut self, c: u8) -> Result<(), Error> {
+        if self.peek() == Some(c) {
+            self.pos += 1;
+            Ok(())
+        }else{
+            Err(self.error(&format!("expected '{}'", c as char)))
+        }
+    }
+
+    fn value(&mut self) -> Result<Node, Error> {
+        match self.peek() {
+            Some(b'{') => {
+                self.pos += 1;
+                let mut fields = Vec::new();
+                while self.peek() != Some(b'}') {
+                    let key = self.string()?;
+                    self.expect(b':')?;
+                    fields.push((key, self.value()?));
+                    self.separator(b'}')?;
+                }
+                self.pos += 1;
+                Ok(Node::Object(fields))
+            },
+            Some(b'[') => {
+                self.pos += 1;
+                let mut items = Vec::new();
+                while self.peek() != Some(b']') {
+                    items.push(self.value()?);
+                    self.separator(b']')?;
+                }
+                self.pos += 1;
+                Ok(Node::List(items))
+            },
+            Some(b'"') => self.string().map(Node::Str),
+            _ if self.s[self.pos..].starts_with("null") => {
+                self.pos += 4;
+                Ok(Node::Null)
+            },
+            _ => Err(self.error("expected a value")),
+        }
+    }
+
+    fn separator(&mut self, close: u8) -> Result<(), Error> {
+        match self.peek() {
+            Some(b',') => {
+                self.pos += 1;
+                Ok(())
+            },
+            Some(c) if c == close => Ok(()),
+            _ => Err(self.error(&format!("expected ',' or '{}'", close as char))),
+        }
+    }
+
+    // Quotes are not escaped, so a quote only closes the string when it is
+    // followed by something that may follow a string.
+    fn string(&mut self) -> Result<String, Error> {
+        self.expect(b'"')?;
+        let start = self.pos;
+        let bytes = self.s.as_bytes();
+        let mut i = start;
+        while i < bytes.len() {
+            if bytes[i] == b'"' {
+                let next = self.s[i + 1..].trim_start().as_bytes().first().cloned();
+                match next {
+                    None | Some(b',') | Some(b':') | Some(b'}') | Some(b']') => {
+                        self.pos = i + 1;
+                        return Ok(self.s[start..i].to_string());
+                    },
+                    _ => (),
+                }
+            }
+            i += 1;
+        }
+        Err(self.error("unterminated string"))
+    }
+}
+
+fn parse(s: &str) -> Result<Node, Error> {
+    let mut parser = Parser{s, pos: 0};
+    let node = parser.value()?;
+    if parser.peek().is_some() {
+        return Err(parser.error("trailing data"));
+    }
+    Ok(node)
+}
+
+fn yes(node: &Node, key: &str) -> bool {
+    node.str(key) == Some("yes")
+}
+
+fn media_from_node(name: &str, node: &Node) -> Result<VlmMedia, Error> {
+    let kind = match node.str("type") {
+        Some("broadcast") => VlmMediaKind::Broadcast,
+        Some("vod") => VlmMediaKind::Vod,
+        _ => return Err(Error::Parse(format!("VLM media \"{}\" has an unknown type", name))),
+    };
+    // Inputs may also be printed with their numbers as names.
+    let strings = |key| {
+        let items: Vec<&Node> = match node.get(key) {
+            Some(Node::List(items)) => items.iter().collect(),
+            Some(Node::Object(fields)) => fields.iter().map(|f| &f.1).collect(),
+            _ => Vec::new(),
+        };
+        items.into_iter().filter_map(|item| match *item {
+            Node::Str(ref s) => Some(s.clone()),
+            _ => None,
+        }).collect()
+    };
+    let instances = match node.get("instances") {
+        Some(Node::Object(fields)) => fields.iter().map(|f| instance_from_node(&f.1)).collect(),
+        _ => Vec::new(),
+    };
+
+    Ok(VlmMedia {
+        name: name.to_string(),
+        kind,
+        enabled: yes(node, "enabled"),
+        loop_broadcast: yes(node, "loop"),
+        mux: node.str("mux").filter(|s| !s.is_empty()).map(|s| s.to_string()),
+        inputs: strings("inputs"),
+        output: node.str("output").filter(|s| !s.is_empty()).map(|s| s.to_string()),
+        options: strings("options"),
+        instances,
+    })
+}
+
+fn instance_from_node(node: &Node) -> VlmInstance {
+    VlmInstance {
+        name: node.str("name").unwrap_or("default").to_string(),
+        state: match node.str("state") {
+            Some("playing") => VlmInstanceState::Playing,
+            Some("paused") => VlmInstanceState::Paused,
+            _ => VlmInstanceState::Stopped,
+        },
+        position: node.parse_or_default("position"),
+        time: node.parse_or_default("time"),
+        length: node.parse_or_default("length"),
+        rate: node.parse_or_default("rate"),
+        title: node.parse_or_default("title"),
+        chapter: node.parse_or_default("chapter"),
+        seekable: node.str("can-seek") == Some("1"),
+        playlist_index: node.parse_or_default("playlistindex"),
+    }
+}
+
+/// Parse the answer of `libvlc_vlm_show_media` for the media `name`.
+pub(crate) fn parse_media(name: &str, s: &str) -> Result<VlmMedia, Error> {
+    media_from_node(name, &parse(s)?)
+}
+
+/// Parse the answer of `libvlc_vlm_show_media` for all media.
+///
+/// Media printed without name, as libvlc 3 does, are `None`: they can only be
+/// described by showing them by name.
+pub(crate) fn parse_media_list(s: &str) -> Result<Vec<Option<VlmMedia>>, Error> {
+    let answer = parse(s)?;
+    let items = match answer.get("media") {
+        Some(Node::List(items)) => items,
+        // Without any media, "media" only holds the "( 0 broadcast - 0 vod )" summary.
+        Some(Node::Str(_)) | Some(Node::Null) => return Ok(Vec::new()),
+        _ => return Err(Error::Parse("VLM answer without media list".to_string())),
+    };
+    items.iter().map(|item| item.str("name").map(|name| media_from_node(name, item)).transpose()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Answers laid out like libvlc 3 prints them, tabs shown as spaces.
+
+    const SHOW_BROADCAST: &str = r##"{
+    "type": "broadcast",
+    "enabled": "yes",
+    "loop": "yes",
+    "inputs": {
+        "1": "file:///videos/a "quoted".mp4",
+        "2": "dvd:///dev/sr0"
+    },
+    "output": "#std{access=udp,mux=ts,dst=239.0.0.1:1234}",
+    "options": [
+        null,
+        null 
+        
+    ],
+    "instances": {
+        "instance": {
+            "name": "default",
+            "state": "playing",
+            "position": "0.250000",
+            "time": "15000000",
+            "length": "60000000",
+            "rate": "1.000000",
+            "title": "0",
+            "chapter": "0",
+            "can-seek": "1",
+            "playlistindex": "1" 
+            
+        },
+        "instance": {
+            "name": "second",
+            "state": "paused" 
+            
+        } 
+        
+    } 
+    
+} 
+
+"##;
+
+    const SHOW_VOD: &str = r##"{
+    "type": "vod",
+    "enabled": "no",
+    "mux": null,
+    "inputs": null,
+    "output": "",
+    "options": null,
+    "instances": null 
+    
+} 
+
+"##;
+
+    const SHOW_EMPTY: &str = r##"{
+    "media": "( 0 broadcast - 0 vod )",
+    "schedule": null 
+    
+}
+"##;
+
+    const SHOW_ALL: &str = r##"{
+    "media": [
+        {
+            "type": "broadcast",
+            "enabled": "yes",
+            "loop": "no",
+            "inputs": [
+                "file:///videos/a.mp4" 
+                
+            ],
+            "output": "",
+            "options": null,
+            "instances": null 
+            
+        },
+        {
+            "type": "vod",
+            "enabled": "yes",
+            "mux": "ts",
+            "inputs": [
+                "file:///videos/b.mp4" 
+                
+            ],
+            "output": "",
+            "options": null,
+            "instances": null 
+            
+        } 
+        
+    ],
+    "schedule": null 
+    
+}
+"##;
+
+    #[test]
+    fn parse_broadcast() {
+        let media = parse_media("ch1", SHOW_BROADCAST).unwrap();
+        assert_eq!(media.name, "ch1");
+        assert_eq!(media.kind, VlmMediaKind::Broadcast);
+        assert!(media.enabled);
+        assert!(media.loop_broadcast);
+        assert_eq!(media.mux, None);
+        assert_eq!(media.inputs, ["file:///videos/a \"quoted\".mp4", "dvd:///dev/sr0"]);
+        assert_eq!(media.output.as_deref(), Some("#std{access=udp,mux=ts,dst=239.0.0.1:1234}"));
+        assert_eq!(media.instances.len(), 2);
+
+        let instance = &media.instances[0];
+        assert_eq!(instance.name, "default");
+        assert_eq!(instance.state, VlmInstanceState::Playing);
+        assert_eq!(instance.position, 0.25);
+        assert_eq!(instance.time, 15_000_000);
+        assert_eq!(instance.length, 60_000_000);
+        assert_eq!(instance.rate, 1.0);
+        assert!(instance.seekable);
+        assert_eq!(instance.playlist_index, 1);
+
+        // Without input, only the name and state are printed.
+        let instance = &media.instances[1];
+        assert_eq!(instance.name, "second");
+        assert_eq!(instance.state, VlmInstanceState::Paused);
+        assert_eq!(instance.time, 0);
+        assert!(!instance.seekable);
+    }
+
+    #[test]
+    fn options_are_printed_without_text() {
+        let media = parse_media("ch1", SHOW_BROADCAST).unwrap();
+        assert!(media.options.is_empty());
+    }
+
+    #[test]
+    fn parse_null_values() {
+        let media = parse_media("movie", SHOW_VOD).unwrap();
+        assert_eq!(media.name, "movie");
+        assert_eq!(media.kind, VlmMediaKind::Vod);
+        assert!(!media.enabled);
+        assert!(!media.loop_broadcast);
+        assert_eq!(media.mux, None);
+        assert!(media.inputs.is_empty());
+        assert_eq!(media.output, None);
+        assert!(media.options.is_empty());
+        assert!(media.instances.is_empty());
+    }
+
+    #[test]
+    fn parse_empty_list() {
+        assert!(parse_media_list(SHOW_EMPTY).unwrap().is_empty());
+    }
+
+    #[test]
+    fn parse_list_without_names() {
+        let media = parse_media_list(SHOW_ALL).unwrap();
+        assert_eq!(media, [None, None]);
+    }
+
+    #[test]
+    fn parse_list_with_printed_names() {
+        let answer = SHOW_ALL
+            .replacen("{\n            \"type\"", "{\n            \"name\": \"ch1\",\n            \"type\"", 1)
+            .replacen("{\n            \"type\": \"vod\"", "{\n            \"name\": \"movie\",\n            \"type\": \"vod\"", 1);
+        let media: Vec<VlmMedia> = parse_media_list(&answer).unwrap().into_iter().flatten().collect();
+        assert_eq!(media.len(), 2);
+        assert_eq!(media[0].name, "ch1");
+        assert_eq!(media[0].kind, VlmMediaKind::Broadcast);
+        assert_eq!(media[0].inputs, ["file:///videos/a.mp4"]);
+        assert_eq!(media[1].name, "movie");
+        assert_eq!(media[1].kind, VlmMediaKind::Vod);
+        assert_eq!(media[1].mux.as_deref(), Some("ts"));
+    }
+
+    #[test]
+    fn parse_invalid() {
+        assert!(parse_media("ch1", "").is_err());
+        assert!(parse_media("ch1", "{ \"type\": \"broadcast\"").is_err());
+        assert!(parse_media("ch1", "{ \"type\": \"other\" }").is_err());
+        assert!(parse_media_list("{ \"schedule\": null }").is_err());
+    }
+}
@@ -0,0 +1,258 @@
+// Copyright (c) 2015 T. Okubo
+// This file is part of vlc-rs.
+// Licensed under the MIT license, see the LICENSE file.
+
+use std::collections::BTreeMap;
+use std::future::poll_fn;
+use std::pin::Pin;
+use std::sync::{Arc, Condvar, Mutex, Once};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::task::{Context, Poll, Waker};
+use std::thread;
+use std::time::{Duration, Instant};
+use futures_core::Stream;
+use crate::{Error, Event, EventType, EventStream, OverflowPolicy, Media, MediaPlayer, ParseFlag, ParsedStatus, State};
+
+const WAIT_BUFFER: usize = 16;
+
+const STATE_EVENTS: [EventType; 8] = [
+    EventType::MediaPlayerNothingSpecial,
+    EventType::MediaPlayerOpening,
+    EventType::MediaPlayerBuffering,
+    EventType::MediaPlayerPlaying,
+    EventType::MediaPlayerPaused,
+    EventType::MediaPlayerStopped,
+    EventType::MediaPlayerEndReached,
+    EventType::MediaPlayerEncounteredError,
+];
+
+#[derive(Default)]
+struct TimerState {
+    expired: bool,
+    waker: Option<Waker>,
+}
+
+type TimerKey = (Instant, u64);
+
+// Pending timers by deadline, expired by a single helper thread.
+struct TimerQueue {
+    timers: Mutex<BTreeMap<TimerKey, Arc<Mutex<TimerState>>>>,
+    changed: Condvar,
+    next_id: AtomicU64,
+    started: Once,
+}
+
+static TIMERS: TimerQueue = TimerQueue {
+    timers: Mutex::new(BTreeMap::new()),
+    changed: Condvar::new(),
+    next_id: AtomicU64::new(0),
+    started: Once::new(),
+};
+
+impl TimerQueue {
+    fn run(&self) {
+        let mut timers = self.timers.lock().unwrap();
+        loop {
+            let now = Instant::now();
+            match timers.keys().next().cloned() {
+                Some(key) if key.0 <= now => {
+                    let state = timers.remove(&key).unwrap();
+                    let mut state = state.lock().unwrap();
+                    state.expired = true;
+                    if let Some(waker) = state.waker.take() {
+                        waker.wake();
+                    }
+                },
+                Some(key) => timers = self.changed.wait_timeout(timers, key.0 - now).unwrap().0,
+                None => timers = self.changed.wait(timers).unwrap(),
+            }
+        }
+    }
+}
+
+// Timer driven by a helper thread, so that no particular runtime is needed.
+// Dropping it cancels it.
+struct Timer {
+    key: TimerKey,
+    state: Arc<Mutex<TimerState>>,
+}
+
+impl Timer {
+    fn new(timeout: Duration) -> Timer {
+        TIMERS.started.call_once(|| {
+            thread::Builder::new().name("vlc-rs timer".to_owned())
+                .spawn(|| TIMERS.run()).expect("failed to spawn the timer thread");
+        });
+
+        let key = (Instant::now() + timeout, TIMERS.next_id.fetch_add(1, Ordering::Relaxed));
+        let state = Arc::new(Mutex::new(TimerState::default()));
+        let mut timers = TIMERS.timers.lock().unwrap();
+        // Wake the helper thread if this is its new earliest deadline.
+        if timers.keys().next().is_none_or(|first| key < *first) {
+            TIMERS.changed.notify_one();
+        }
+        timers.insert(key, state.clone());
+        Timer{key, state}
+    }
+
+    fn poll_expired(&self, cx: &mut Context<'_>) -> bool {
+        let mut state = self.state.lock().unwrap();
+        if !state.expired {
+            state.waker = Some(cx.waker().clone());
+        }
+        state.expired
+    }
+}
+
+impl Drop for Timer {
+    fn drop(&mut self) {
+        TIMERS.timers.lock().unwrap().remove(&self.key);
+    }
+}
+
+// Wait until `f` returns Some for an event of the stream, or until `timeout` expires (None).
+async fn wait_event<T, F>(stream: &mut EventStream<'_>, timeout: Option<Duration>, mut f: F) -> Option<T>
+    where F: FnMut(Event) -> Option<T>
+{
+    let timer = timeout.map(Timer::new);
+    poll_fn(|cx| {
+        while let Poll::Ready(event) = Pin::new(&mut *stream).poll_next(cx) {
+            match event.map(&mut f) {
+                Some(None) => (),
+                Some(result) => return Poll::Ready(result),
+                None => return Poll::Ready(None),
+            }
+        }
+        match timer {
+            Some(ref timer) if timer.poll_expired(cx) => Poll::Ready(None),
+            _ => Poll::Pending,
+        }
+    }).await
+}
+
+impl MediaPlayer {
+    /// Start playback and wait until the player is actually playing.
+    /// Fails if playback can't be started, an error is encountered or `timeout` expires.
+    pub async fn play_until_playing(&self, timeout: Option<Duration>) -> Result<(), Error> {
+        let em = self.event_manager();
+        let mut stream = em.stream(
+            &[EventType::MediaPlayerPlaying, EventType::MediaPlayerEncounteredError],
+            WAIT_BUFFER, OverflowPolicy::DropOldest)?;
+
+        self.play()?;
+        if self.state() == State::Playing {
+            return Ok(());
+        }
+
+        match wait_event(&mut stream, timeout, |e| match e {
+            Event::MediaPlayerPlaying => Some(Ok(())),
+            // The error is raised on a libvlc thread, its message is not available here.
+            Event::MediaPlayerEncounteredError => Some(Err(Error::Vlc(None))),
+            _ => None,
+        }).await {
+            Some(result) => result,
+            None => Err(Error::Timeout),
+        }
+    }
+
+    /// Wait until the player reaches `state`.
+    /// Returns immediately if the player is already in that state; fails if `timeout` expires.
+    pub async fn wait_for_state(&self, state: State, timeout: Option<Duration>) -> Result<(), Error> {
+        let em = self.event_manager();
+        let mut stream = em.stream(&STATE_EVENTS, WAIT_BUFFER, OverflowPolicy::DropOldest)?;
+
+        if self.state() == state {
+            return Ok(());
+        }
+
+        wait_event(&mut stream, timeout, |_| if self.state() == state { Some(()) }else{ None })
+            .await.ok_or(Error::Timeout)
+    }
+
+    /// Wait until playback reaches the end of the media or an error is encountered.
+    /// Returns the final state, either Ended or Error; fails if `timeout` expires.
+    pub async fn wait_end_or_error(&self, timeout: Option<Duration>) -> Result<State, Error> {
+        let em = self.event_manager();
+        let mut stream = em.stream(
+            &[EventType::MediaPlayerEndReached, EventType::MediaPlayerEncounteredError],
+            WAIT_BUFFER, OverflowPolicy::DropOldest)?;
+
+        match self.state() {
+            s @ State::Ended | s @ State::Error => return Ok(s),
+            _ => (),
+        }
+
+        wait_event(&mut stream, timeout, |e| match e {
+            Event::MediaPlayerEndReached => Some(State::Ended),
+            Event::MediaPlayerEncounteredError => Some(State::Error),
+            _ => None,
+        }).await.ok_or(Error::Timeout)
+    }
+
+    /// Set the movie time (in ms) and wait for the next time change.
+    /// Returns the new time (in ms); fails if `timeout` expires.
+    pub async fn seek_and_wait(&self, time: i64, timeout: Option<Duration>) -> Result<i64, Error> {
+        let em = self.event_manager();
+        let mut stream = em.stream(&[EventType::MediaPlayerTimeChanged], 1, OverflowPolicy::DropOldest)?;
+
+        self.set_time(time);
+
+        wait_event(&mut stream, timeout, |e| match e {
+            Event::MediaPlayerTimeChanged(time) => Some(time),
+            _ => None,
+        }).await.ok_or(Error::Timeout)
+    }
+}
+
+impl Media {
+    /// Parse the media with options and wait for parsing to finish.
+    /// Asynchronous counterpart of parse_and_wait(). If `timeout` expires, parsing is stopped
+    /// and Timeout returned. If the media was already parsed, its status is returned immediately.
+    ///
+    /// This replaces the former synchronous `parse_async()`, which only started parsing
+    /// like `parse_with_options()` does.
+    pub async fn parse_async(&self, flags: &[ParseFlag], timeout: Option<Duration>) -> Result<ParsedStatus, Error> {
+        let em = self.event_manager();
+        let mut stream = em.stream(&[EventType::MediaParsedChanged], WAIT_BUFFER, OverflowPolicy::DropOldest)?;
+
+        self.parse_with_options(flags, timeout)?;
+        if let Some(status) = self.parsed_status() {
+            return Ok(status);
+        }
+
+        let status = wait_event(&mut stream, timeout, |e| match e {
+            Event::MediaParsedChanged(status) if status > 0 => {
+                Some(ParsedStatus::from_raw(status as u32).unwrap_or(ParsedStatus::Failed))
+            },
+            _ => None,
+        }).await;
+
+        Ok(status.unwrap_or_else(|| {
+            self.parse_stop();
+            ParsedStatus::Timeout
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn timer_expires() {
+        let timer = Timer::new(Duration::from_millis(20));
+        let mut cx = Context::from_waker(Waker::noop());
+        assert!(!timer.poll_expired(&mut cx));
+        thread::sleep(Duration::from_millis(200));
+        assert!(timer.poll_expired(&mut cx));
+    }
+
+    #[test]
+    fn dropped_timer_is_cancelled() {
+        let timer = Timer::new(Duration::from_secs(3600));
+        let key = timer.key;
+        assert!(TIMERS.timers.lock().unwrap().contains_key(&key));
+        drop(timer);
+        assert!(!TIMERS.timers.lock().unwrap().contains_key(&key));
+    }
+}
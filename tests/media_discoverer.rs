@@ -0,0 +1,53 @@
+// Copyright (c) 2015 T. Okubo
+// This file is part of vlc-rs.
+// Licensed under the MIT license, see the LICENSE file.
+
+// Discovery of a local directory, without any network access.
+//
+// The "video_dir" service adds the XDG videos directory, which libvlc reads
+// from $XDG_CONFIG_HOME/user-dirs.dirs on Linux. This test points it at a
+// temporary directory. It changes the environment before any libvlc instance
+// exists, so it must stay the only test of this binary.
+
+#![cfg(target_os = "linux")]
+
+use std::env;
+use std::fs;
+use std::process;
+use std::time::{Duration, Instant};
+use vlc::{DiscovererCategory, Event, EventType, Instance, MediaDiscoverer, Mrl};
+
+#[test]
+fn discovers_videos_directory() {
+    let root = env::temp_dir().join(format!("vlc-rs-discoverer-{}", process::id()));
+    let videos = root.join("Videos");
+    fs::create_dir_all(&videos).unwrap();
+    fs::write(root.join("user-dirs.dirs"), format!("XDG_VIDEOS_DIR=\"{}\"\n", videos.display())).unwrap();
+    env::set_var("XDG_CONFIG_HOME", &root);
+
+    let instance = Instance::new().unwrap();
+    let services = instance.media_discoverer_list_get(DiscovererCategory::LocalDirs);
+    assert!(services.iter().any(|s| s.name == "video_dir"), "no video_dir service in {:?}", services);
+
+    let discoverer = MediaDiscoverer::new(&instance, "video_dir").unwrap();
+    let list = discoverer.media_list().unwrap();
+    let events = list.event_manager().subscribe(&[EventType::MediaListItemAdded]).unwrap();
+    discoverer.start().unwrap();
+    assert!(discoverer.is_running());
+
+    let expected = Mrl::from_path(&videos).unwrap().to_string();
+    let same = |mrl: Option<String>| mrl.is_some_and(|mrl| mrl.trim_end_matches('/') == expected);
+    let deadline = Instant::now() + Duration::from_secs(10);
+    let mut found = false;
+    while !found {
+        let timeout = deadline.checked_duration_since(Instant::now()).expect("videos directory not discovered");
+        if let Ok(Event::MediaListItemAdded(md, _)) = events.recv_timeout(timeout) {
+            found = same(md.mrl());
+        }
+    }
+
+    drop(events);
+    discoverer.stop();
+    assert!(!discoverer.is_running());
+    let _ = fs::remove_dir_all(&root);
+}
@@ -0,0 +1,117 @@
+// Copyright (c) 2015 T. Okubo
+// This file is part of vlc-rs.
+// Licensed under the MIT license, see the LICENSE file.
+
+// Renderer discovery against a local mDNS stand-in.
+//
+// The stand-in multicasts the answers a Chromecast gives to the
+// "_googlecast._tcp.local" query, so the "microdns_renderer" service finds a
+// renderer without any real device on the network. Run it with
+// `cargo test --test renderer_discoverer -- --ignored`.
+
+use std::net::UdpSocket;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+use vlc::{Event, EventType, Instance, RendererDiscoverer};
+
+const TYPE_A: u16 = 1;
+const TYPE_PTR: u16 = 12;
+const TYPE_TXT: u16 = 16;
+const TYPE_SRV: u16 = 33;
+
+fn push_name(out: &mut Vec<u8>, name: &str) {
+    for label in name.split('.') {
+        out.push(label.len() as u8);
+        out.extend_from_slice(label.as_bytes());
+    }
+    out.push(0);
+}
+
+fn push_record(out: &mut Vec<u8>, name: &str, rr_type: u16, data: &[u8]) {
+    push_name(out, name);
+    out.extend_from_slice(&rr_type.to_be_bytes());
+    out.extend_from_slice(&1u16.to_be_bytes()); // class IN
+    out.extend_from_slice(&120u32.to_be_bytes()); // TTL
+    out.extend_from_slice(&(data.len() as u16).to_be_bytes());
+    out.extend_from_slice(data);
+}
+
+// An mDNS response announcing a cast device at 127.0.0.1:8009.
+fn announcement() -> Vec<u8> {
+    let service = "_googlecast._tcp.local";
+    let instance = "vlc-rs-test._googlecast._tcp.local";
+    let host = "vlc-rs-test.local";
+
+    // Header: id 0, authoritative response, no question, 4 answers.
+    let mut out = vec![0, 0, 0x84, 0, 0, 0, 0, 4, 0, 0, 0, 0];
+
+    let mut ptr = Vec::new();
+    push_name(&mut ptr, instance);
+    push_record(&mut out, service, TYPE_PTR, &ptr);
+
+    let mut srv = vec![0, 0, 0, 0]; // priority, weight
+    srv.extend_from_slice(&8009u16.to_be_bytes());
+    push_name(&mut srv, host);
+    push_record(&mut out, instance, TYPE_SRV, &srv);
+
+    let mut txt = Vec::new();
+    for entry in &["fn=vlc-rs test", "md=Chromecast", "ca=5"] {
+        txt.push(entry.len() as u8);
+        txt.extend_from_slice(entry.as_bytes());
+    }
+    push_record(&mut out, instance, TYPE_TXT, &txt);
+
+    push_record(&mut out, host, TYPE_A, &[127, 0, 0, 1]);
+    out
+}
+
+#[test]
+#[ignore = "needs libvlc built with the microdns_renderer plugin and multicast on loopback"]
+fn discovers_mdns_renderer() {
+    let instance = Instance::new().unwrap();
+    let services = instance.renderer_discoverer_list_get();
+    assert!(services.iter().any(|s| s.name == "microdns_renderer"), "no microdns_renderer service in {:?}", services);
+
+    let discoverer = RendererDiscoverer::new(&instance, "microdns_renderer").unwrap();
+    let events = discoverer.event_manager().subscribe(&[EventType::RendererDiscovererItemAdded]).unwrap();
+    discoverer.start().unwrap();
+
+    // Answer the queries of the discoverer by announcing the device until it is found.
+    let stop = Arc::new(AtomicBool::new(false));
+    let stand_in = {
+        let stop = stop.clone();
+        let socket = UdpSocket::bind("0.0.0.0:0").unwrap();
+        socket.set_multicast_loop_v4(true).unwrap();
+        let packet = announcement();
+        thread::spawn(move || {
+            while !stop.load(Ordering::Relaxed) {
+                let _ = socket.send_to(&packet, "224.0.0.251:5353");
+                thread::sleep(Duration::from_millis(200));
+            }
+        })
+    };
+
+    let deadline = Instant::now() + Duration::from_secs(10);
+    let item = loop {
+        let timeout = deadline.checked_duration_since(Instant::now()).expect("renderer not discovered");
+        if let Ok(Event::RendererDiscovererItemAdded(item)) = events.recv_timeout(timeout) {
+            if item.name() == "vlc-rs test" {
+                break item;
+            }
+        }
+    };
+    stop.store(true, Ordering::Relaxed);
+    stand_in.join().unwrap();
+
+    assert_eq!(item.renderer_type(), "chromecast");
+    assert!(item.can_audio());
+    assert!(item.can_video());
+
+    // Items stay valid after the discoverer is gone.
+    drop(events);
+    discoverer.stop();
+    drop(discoverer);
+    assert_eq!(item.clone().name(), "vlc-rs test");
+}